@@ -0,0 +1,157 @@
+// latency of cheap requests with and without large batches running alongside them
+//
+//     cargo bench --bench validation_bench
+//
+// prints p50/p99 per case, `/health` and `/validate` under batch load should stay
+// close to their idle numbers since batches run on the pipeline's own pool and
+// single validations never queue behind them
+
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use actix_web::{App, HttpServer, web};
+use criterion::{Criterion, criterion_group, criterion_main};
+use email_processor::api::{AppState, routes};
+use email_processor::domain::EmailSignature;
+use email_processor::infrastructure::Config;
+use tokio::runtime::Runtime;
+
+const BATCH_SIZE: usize = 1000;
+const LOAD_CLIENTS: usize = 4;
+const SERVER_WORKERS: usize = 2;
+
+// serve the api on a free local port from its own thread, the way main does
+fn start_server() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let state = web::Data::new(AppState::new(Config::load().unwrap()));
+
+    std::thread::spawn(move || {
+        actix_web::rt::System::new().block_on(async move {
+            HttpServer::new(move || {
+                App::new()
+                    .app_data(state.clone())
+                    .configure(routes::configure)
+            })
+            .workers(SERVER_WORKERS)
+            .listen(listener)
+            .unwrap()
+            .run()
+            .await
+        })
+    });
+
+    format!("http://{}", addr)
+}
+
+async fn wait_until_ready(client: &reqwest::Client, base: &str) {
+    for _ in 0..100 {
+        if client.get(format!("{}/health", base)).send().await.is_ok() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    panic!("server did not start");
+}
+
+fn batch_body() -> String {
+    let signatures: Vec<EmailSignature> = (0..BATCH_SIZE)
+        .map(|i| {
+            EmailSignature::builder()
+                .name(format!("Person {}", i))
+                .email(format!("person{}@example.com", i))
+                .title("Engineer")
+                .company("Example Corp")
+                .build()
+        })
+        .collect();
+
+    serde_json::json!({ "signatures": signatures }).to_string()
+}
+
+// clients posting batches back to back until stopped
+fn start_batch_load(rt: &Runtime, client: &reqwest::Client, base: &str) -> Arc<AtomicBool> {
+    let running = Arc::new(AtomicBool::new(true));
+    let body = batch_body();
+
+    for _ in 0..LOAD_CLIENTS {
+        let running = running.clone();
+        let client = client.clone();
+        let url = format!("{}/api/v1/signatures/validate-batch", base);
+        let body = body.clone();
+
+        rt.spawn(async move {
+            while running.load(Ordering::Relaxed) {
+                let _ = client
+                    .post(&url)
+                    .header("Content-Type", "application/json")
+                    .body(body.clone())
+                    .send()
+                    .await;
+            }
+        });
+    }
+
+    running
+}
+
+fn percentile(samples: &mut [Duration], p: f64) -> Duration {
+    samples.sort();
+    let index = ((samples.len() as f64 * p).ceil() as usize).clamp(1, samples.len()) - 1;
+    samples[index]
+}
+
+fn latency(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let client = reqwest::Client::new();
+    let base = start_server();
+    rt.block_on(wait_until_ready(&client, &base));
+
+    let signature = serde_json::to_string(&EmailSignature::builder().build()).unwrap();
+    let mut group = c.benchmark_group("latency");
+    group.measurement_time(Duration::from_secs(10));
+
+    for (load, loaded) in [("idle", false), ("batch_load", true)] {
+        let running = loaded.then(|| start_batch_load(&rt, &client, &base));
+
+        for endpoint in ["health", "validate"] {
+            let mut samples = Vec::new();
+
+            group.bench_function(format!("{}/{}", endpoint, load), |b| {
+                b.iter_custom(|iters| {
+                    rt.block_on(async {
+                        let start = Instant::now();
+                        for _ in 0..iters {
+                            let sent = Instant::now();
+                            let request = match endpoint {
+                                "health" => client.get(format!("{}/health", base)),
+                                _ => client
+                                    .post(format!("{}/api/v1/signatures/validate", base))
+                                    .header("Content-Type", "application/json")
+                                    .body(signature.clone()),
+                            };
+                            request.send().await.unwrap().bytes().await.unwrap();
+                            samples.push(sent.elapsed());
+                        }
+                        start.elapsed()
+                    })
+                })
+            });
+
+            let p50 = percentile(&mut samples, 0.50);
+            let p99 = percentile(&mut samples, 0.99);
+            println!("{}/{}: p50 {:?}, p99 {:?}", endpoint, load, p50, p99);
+        }
+
+        if let Some(running) = running {
+            running.store(false, Ordering::Relaxed);
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, latency);
+criterion_main!(benches);
@@ -1,11 +1,47 @@
-use actix_web::{HttpResponse, Result, web};
+use actix_web::{
+    HttpRequest, HttpResponse, Result,
+    http::Method,
+    http::header::{self, AcceptLanguage},
+    web,
+};
+use base64::Engine;
 use serde::{Deserialize, Serialize};
 use std::time::Instant;
+use tokio::sync::broadcast;
 use tracing::info;
+use uuid::Uuid;
 
 use crate::api::state::AppState;
 use crate::domain::models::*;
+use crate::domain::{AssetKind, PolicyImpact, RulesConfig};
 use crate::error::AppError;
+use crate::i18n::DEFAULT_LOCALE;
+use crate::infrastructure::webhook;
+use crate::pipeline::{Cancellation, Job, JobUpdate, RevalidationReport};
+use crate::rendering::{BudgetStatus, SizeReport, accessibility, budget, vcard};
+
+// number of accessibility findings in a rendered signature
+const ACCESSIBILITY_HEADER: &str = "X-Accessibility-Findings";
+
+// anything no route matches
+pub async fn not_found(req: HttpRequest) -> Result<HttpResponse, AppError> {
+    Err(AppError::NotFound(format!(
+        "No resource at {} {}",
+        req.method(),
+        req.path()
+    )))
+}
+
+// for a path that exists, requested with a method it doesn't take
+pub async fn method_not_allowed(
+    req: HttpRequest,
+    allowed: &'static [Method],
+) -> Result<HttpResponse, AppError> {
+    Err(AppError::MethodNotAllowed(
+        format!("{} is not supported on {}", req.method(), req.path()),
+        allowed,
+    ))
+}
 
 // health check endpoint
 pub async fn health() -> Result<HttpResponse> {
@@ -19,8 +55,10 @@ pub async fn health() -> Result<HttpResponse> {
 pub async fn validate_signature(
     state: web::Data<AppState>,
     signature: web::Json<EmailSignature>,
+    accept_language: Option<web::Header<AcceptLanguage>>,
 ) -> Result<HttpResponse, AppError> {
     let start = Instant::now();
+    let locale = negotiate_locale(&state, accept_language);
 
     info!(
         signature_id = %signature.id,
@@ -29,7 +67,8 @@ pub async fn validate_signature(
     );
 
     // validate the signature via pipeline
-    let result = state.pipeline.process_single(signature.into_inner()).await;
+    let _permit = state.pipeline.admit(1, false)?;
+    let mut result = state.pipeline.process_single(signature.into_inner()).await;
 
     let duration = start.elapsed();
     info!(
@@ -41,15 +80,20 @@ pub async fn validate_signature(
 
     crate::infrastructure::metrics::record_validation(result.valid);
 
-    Ok(HttpResponse::Ok().json(result))
+    state.messages.localize(&locale, &mut result.errors);
+    Ok(HttpResponse::Ok()
+        .insert_header((header::CONTENT_LANGUAGE, locale))
+        .json(result))
 }
 
 // batch validation
 pub async fn validate_batch(
     state: web::Data<AppState>,
     request: web::Json<BatchValidateResult>,
+    accept_language: Option<web::Header<AcceptLanguage>>,
 ) -> Result<HttpResponse, AppError> {
     let start = Instant::now();
+    let locale = negotiate_locale(&state, accept_language);
     let batch_size = request.signatures.len();
 
     // validate batch size
@@ -60,44 +104,652 @@ pub async fn validate_batch(
         )));
     }
 
+    let _permit = state.pipeline.admit(batch_size, true)?;
+
     info!(batch_size = batch_size, "Processing batch validation");
 
+    // actix drops this future when the client disconnects, the guard then stops the pipeline
+    let cancellation = Cancellation::new();
+    let _cancel_on_disconnect = cancellation.guard();
+
     // use pipeline for batch validation
-    let results = state.pipeline.process_batch(request.into_inner().signatures).await;
+    let mut results = state
+        .pipeline
+        .process_batch_cancellable(request.into_inner().signatures, &cancellation)
+        .await;
 
-    // calculate summary
-    let valid_count = results.iter().filter(|r| r.valid).count();
-    let invalid_count = batch_size - valid_count;
-    let duration = start.elapsed();
+    let summary = BatchSummary::new(batch_size, &results, start.elapsed().as_millis());
 
     info!(
         batch_size = batch_size,
-        valid = valid_count,
-        invalid = invalid_count,
-        duration_ms = duration.as_millis(),
+        valid = summary.valid,
+        invalid = summary.invalid,
+        timed_out = summary.timed_out,
+        duration_ms = summary.processing_time_ms,
         "Batch validation complete"
     );
 
-    for result in &results {
+    for result in &mut results {
         crate::infrastructure::metrics::record_validation(result.valid);
+        state.messages.localize(&locale, &mut result.errors);
+    }
+
+    let response = BatchValidateResponse { results, summary };
+
+    Ok(HttpResponse::Ok()
+        .insert_header((header::CONTENT_LANGUAGE, locale))
+        .json(response))
+}
+
+// validate a batch in the background, poll or cancel it under /api/v1/jobs/{id}
+pub async fn create_job(
+    state: web::Data<AppState>,
+    request: web::Json<BatchValidateRequest>,
+) -> Result<HttpResponse, AppError> {
+    let batch_size = request.signatures.len();
+    if batch_size > state.config.pipeline.max_batch_size {
+        return Err(AppError::Validation(format!(
+            "Batch size {} exceeds maximum of {}",
+            batch_size, state.config.pipeline.max_batch_size
+        )));
+    }
+
+    if let Some(url) = &request.callback_url {
+        webhook::check_callback_url(&state.config.webhooks, url)
+            .await
+            .map_err(AppError::Validation)?;
+    }
+
+    let permit = state.pipeline.admit(batch_size, true)?;
+    let request = request.into_inner();
+    let job = state
+        .jobs
+        .submit(request.signatures, request.callback_url, permit)
+        .await;
+
+    Ok(HttpResponse::Accepted()
+        .insert_header((header::LOCATION, format!("/api/v1/jobs/{}", job.id)))
+        .json(JobResponse::new(job)))
+}
+
+// a job's status and the results so far
+pub async fn get_job(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    accept_language: Option<web::Header<AcceptLanguage>>,
+) -> Result<HttpResponse, AppError> {
+    let locale = negotiate_locale(&state, accept_language);
+    let id = path.into_inner();
+    let mut job = state
+        .jobs
+        .get(id)
+        .ok_or_else(|| AppError::NotFound(format!("Job {} not found", id)))?;
+    for result in &mut job.results {
+        state.messages.localize(&locale, &mut result.errors);
+    }
+
+    Ok(HttpResponse::Ok()
+        .insert_header((header::CONTENT_LANGUAGE, locale))
+        .json(JobResponse::new(job)))
+}
+
+// live progress of a job as server-sent events: a `progress` event now and after every chunk,
+// then a `summary` event once the job is finished, which ends the stream
+pub async fn job_events(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    accept_language: Option<web::Header<AcceptLanguage>>,
+) -> Result<HttpResponse, AppError> {
+    let locale = negotiate_locale(&state, accept_language);
+    let id = path.into_inner();
+    let (job, updates) = state
+        .jobs
+        .subscribe(id)
+        .ok_or_else(|| AppError::NotFound(format!("Job {} not found", id)))?;
+
+    let first = if job.status.is_finished() {
+        JobUpdate::Finished(state.jobs.notification(&job))
+    } else {
+        JobUpdate::Progress(job.progress(Vec::new()))
+    };
+    let messages = state.messages.clone();
+    let content_language = locale.clone();
+
+    let events = futures_util::stream::unfold(Some((Some(first), updates)), move |next| {
+        let messages = messages.clone();
+        let locale = locale.clone();
+        async move {
+            let (pending, mut updates) = next?;
+            let update = match pending {
+                Some(update) => update,
+                None => loop {
+                    match updates.recv().await {
+                        Ok(update) => break update,
+                        Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(broadcast::error::RecvError::Closed) => return None,
+                    }
+                },
+            };
+
+            let event = match update {
+                JobUpdate::Progress(mut progress) => {
+                    for signature in &mut progress.latest_errors {
+                        messages.localize(&locale, &mut signature.errors);
+                    }
+                    sse_event("progress", &progress)
+                }
+                JobUpdate::Finished(notification) => {
+                    let event = sse_event("summary", &notification);
+                    return Some((Ok::<_, actix_web::Error>(event), None));
+                }
+            };
+            Some((Ok(event), Some((None, updates))))
+        }
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header((header::CACHE_CONTROL, "no-cache"))
+        // compressing would hold events back until the encoder's buffer fills
+        .insert_header(header::ContentEncoding::Identity)
+        .insert_header((header::CONTENT_LANGUAGE, content_language))
+        .streaming(events))
+}
+
+fn sse_event(name: &str, data: &impl Serialize) -> web::Bytes {
+    let data = serde_json::to_string(data).expect("event data serializes");
+    web::Bytes::from(format!("event: {}\ndata: {}\n\n", name, data))
+}
+
+// signatures jobs gave up on after retrying, across all jobs still kept
+pub async fn list_dead_letters(state: web::Data<AppState>) -> Result<HttpResponse, AppError> {
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "dead_letters": state.jobs.dead_letters()
+    })))
+}
+
+// stop a job, the results computed so far are kept
+pub async fn cancel_job(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let id = path.into_inner();
+    let job = state
+        .jobs
+        .cancel(id)
+        .await
+        .ok_or_else(|| AppError::NotFound(format!("Job {} not found", id)))?;
+
+    Ok(HttpResponse::Ok().json(JobResponse::new(job)))
+}
+
+// re-run the current rules over every stored signature now instead of waiting for the schedule
+pub async fn run_revalidation(
+    state: web::Data<AppState>,
+    accept_language: Option<web::Header<AcceptLanguage>>,
+) -> Result<HttpResponse, AppError> {
+    let locale = negotiate_locale(&state, accept_language);
+    let report = state.revalidator.run().await;
+    Ok(revalidation_response(&state, &locale, report))
+}
+
+// what the latest revalidation found, newly failing signatures first of all
+pub async fn revalidation_report(
+    state: web::Data<AppState>,
+    accept_language: Option<web::Header<AcceptLanguage>>,
+) -> Result<HttpResponse, AppError> {
+    let locale = negotiate_locale(&state, accept_language);
+    let report = state
+        .revalidator
+        .last_report()
+        .ok_or_else(|| AppError::NotFound("No revalidation has run yet".to_string()))?;
+    Ok(revalidation_response(&state, &locale, report))
+}
+
+fn revalidation_response(
+    state: &AppState,
+    locale: &str,
+    mut report: RevalidationReport,
+) -> HttpResponse {
+    for change in report
+        .newly_failing
+        .iter_mut()
+        .chain(report.recovered.iter_mut())
+    {
+        state.messages.localize(locale, &mut change.errors);
+    }
+
+    HttpResponse::Ok()
+        .insert_header((header::CONTENT_LANGUAGE, locale.to_string()))
+        .json(report)
+}
+
+// how a candidate rule set would change validation outcomes, nothing is stored or applied
+pub async fn policy_dry_run(
+    state: web::Data<AppState>,
+    request: web::Json<DryRunRequest>,
+) -> Result<HttpResponse, AppError> {
+    let request = request.into_inner();
+    let candidate = request
+        .rules
+        .build()
+        .map_err(|e| AppError::Validation(format!("Invalid candidate rules: {}", e)))?;
+    // invalid configured rules are ignored by the pipeline as well
+    let current = state.config.rules.build().unwrap_or_default();
+
+    // a supplied sample is client load like any batch, stored signatures are checked like a revalidation
+    let (signatures, _permit) = match request.signatures {
+        Some(sample) => {
+            if sample.len() > state.config.pipeline.max_batch_size {
+                return Err(AppError::Validation(format!(
+                    "Batch size {} exceeds maximum of {}",
+                    sample.len(),
+                    state.config.pipeline.max_batch_size
+                )));
+            }
+            let permit = state.pipeline.admit(sample.len(), true)?;
+            let sample = sample
+                .into_iter()
+                .map(|mut sig| {
+                    state.pipeline.normalize(&mut sig);
+                    sig
+                })
+                .collect();
+            (sample, Some(permit))
+        }
+        None => (state.signatures.list(), None),
+    };
+
+    let current = state.validator_with_rules(current);
+    let candidate = state.validator_with_rules(candidate);
+    let impact = web::block(move || {
+        PolicyImpact::compare(
+            &current.validate_batch(&signatures),
+            &candidate.validate_batch(&signatures),
+        )
+    })
+    .await
+    .map_err(|e| AppError::Internal(e.to_string()))?;
+
+    info!(
+        checked = impact.checked,
+        became_invalid = impact.became_invalid,
+        became_valid = impact.became_valid,
+        "Policy dry run complete"
+    );
+
+    Ok(HttpResponse::Ok().json(impact))
+}
+
+// validate a signature and store it if it passes
+pub async fn submit_signature(
+    state: web::Data<AppState>,
+    signature: web::Json<EmailSignature>,
+    accept_language: Option<web::Header<AcceptLanguage>>,
+) -> Result<HttpResponse, AppError> {
+    let sig = signature.into_inner();
+    let locale = negotiate_locale(&state, accept_language);
+
+    info!(signature_id = %sig.id, email = %sig.email, "Submitting signature");
+
+    let _permit = state.pipeline.admit(1, false)?;
+    let item = state.pipeline.run_single(sig).await;
+    let mut result = item.result.clone();
+    crate::infrastructure::metrics::record_validation(result.valid);
+
+    if !result.valid {
+        state.messages.localize(&locale, &mut result.errors);
+        return Ok(HttpResponse::UnprocessableEntity()
+            .insert_header((header::CONTENT_LANGUAGE, locale))
+            .json(result));
+    }
+
+    // valid but stopped by a later stage, i.e. rendering failed and it wasn't stored
+    if item.is_halted() {
+        return Err(AppError::Render(result.warnings.pop().unwrap_or_default()));
+    }
+    // stored by the pipeline when it has a persist stage, as normalized and enriched there
+    if !state.pipeline.persists() {
+        state.signatures.put(item.signature);
+    }
+    Ok(HttpResponse::Created().json(result))
+}
+
+// locale for validation messages, from the Accept-Language header
+fn negotiate_locale(
+    state: &AppState,
+    accept_language: Option<web::Header<AcceptLanguage>>,
+) -> String {
+    accept_language
+        .map(|header| state.messages.negotiate(&header.ranked()))
+        .unwrap_or_else(|| DEFAULT_LOCALE.to_string())
+}
+
+// render a stored signature with its template
+// signatures over their size budget are refused with a breakdown of what takes up the space
+pub async fn render_signature(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    options: web::Query<RenderOptions>,
+) -> Result<HttpResponse, AppError> {
+    let sig = find_signature(&state, path.into_inner())?;
+    let mut html = state.templates.render(sig.template_id, &sig)?;
+
+    let vcard_href = match options.vcard {
+        VcardEmbed::None => None,
+        VcardEmbed::Link => Some(format!("/api/v1/signatures/{}/vcard", sig.id)),
+        VcardEmbed::Inline => Some(format!(
+            "data:text/vcard;base64,{}",
+            base64::engine::general_purpose::STANDARD.encode(vcard::to_vcard(&sig))
+        )),
+    };
+
+    if let Some(href) = vcard_href {
+        html.push_str(&format!(
+            r#"<br><a href="{}" download="{}" style="font-size: 12px; color: #1a73e8;">Save contact</a>"#,
+            href,
+            vcard::file_name(&sig)
+        ));
+    }
+
+    let report = size_report(&req, &state, &html)?;
+    if report.status == BudgetStatus::Exceeded {
+        return Ok(HttpResponse::UnprocessableEntity().json(report));
+    }
+
+    // accessibility findings don't block rendering, the full list is under /audit
+    let findings = accessibility::audit(&html);
+    let mut response = HttpResponse::Ok();
+    response
+        .content_type("text/html; charset=utf-8")
+        .insert_header((ACCESSIBILITY_HEADER, findings.len().to_string()));
+    for finding in report.findings.iter().chain(&findings) {
+        response.append_header((header::WARNING, warning(&finding.message)));
     }
+    Ok(response.body(html))
+}
+
+// findings quote text from the page, which may hold line breaks and other characters a
+// header can't carry
+fn warning(message: &str) -> String {
+    let text: String = message
+        .chars()
+        .map(|c| match c {
+            '"' => '\'',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect();
+    format!(r#"199 - "{}""#, text)
+}
+
+// size of a stored signature once rendered, against the caller's budget
+pub async fn signature_size(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let sig = find_signature(&state, path.into_inner())?;
+    let html = state.templates.render(sig.template_id, &sig)?;
+
+    Ok(HttpResponse::Ok().json(size_report(&req, &state, &html)?))
+}
+
+// render-time checks on a stored signature: size budget and accessibility
+pub async fn audit_signature(
+    req: HttpRequest,
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    accept_language: Option<web::Header<AcceptLanguage>>,
+) -> Result<HttpResponse, AppError> {
+    let locale = negotiate_locale(&state, accept_language);
+    let sig = find_signature(&state, path.into_inner())?;
+    let html = state.templates.render(sig.template_id, &sig)?;
+
+    let mut audit = RenderAudit {
+        signature_id: sig.id,
+        size: size_report(&req, &state, &html)?,
+        accessibility: accessibility::audit(&html),
+    };
+    state.messages.localize(&locale, &mut audit.size.findings);
+    state.messages.localize(&locale, &mut audit.accessibility);
+
+    Ok(HttpResponse::Ok()
+        .insert_header((header::CONTENT_LANGUAGE, locale))
+        .json(audit))
+}
+
+fn size_report(req: &HttpRequest, state: &AppState, html: &str) -> Result<SizeReport, AppError> {
+    let tenant = authenticated_tenant(req, state)?;
+    let inline_images: Vec<usize> = state
+        .asset_checker
+        .inlined_assets(html)
+        .iter()
+        .map(|a| a.size_bytes)
+        .collect();
+
+    Ok(budget::check(
+        budget::measure(html, &inline_images),
+        state.config.rendering.budget_for(tenant.as_deref()),
+    ))
+}
+
+// tenant of the api key the request carries, none for anonymous requests
+fn authenticated_tenant(req: &HttpRequest, state: &AppState) -> Result<Option<String>, AppError> {
+    let Some(value) = req.headers().get(header::AUTHORIZATION) else {
+        return Ok(None);
+    };
 
-    let response = BatchValidateResponse {
-        results,
-        summary: BatchSummary {
-            total: batch_size,
-            valid: valid_count,
-            invalid: invalid_count,
-            processing_time_ms: duration.as_millis(),
-        },
+    value
+        .to_str()
+        .ok()
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .and_then(|key| state.config.tenants.tenant_for_key(key.trim()))
+        .map(|tenant| Some(tenant.to_string()))
+        .ok_or_else(|| AppError::Unauthorized("Unknown api key".to_string()))
+}
+
+// download a stored signature as a vCard
+pub async fn export_vcard(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+) -> Result<HttpResponse, AppError> {
+    let sig = find_signature(&state, path.into_inner())?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(vcard::VCARD_CONTENT_TYPE)
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!(r#"attachment; filename="{}""#, vcard::file_name(&sig)),
+        ))
+        .body(vcard::to_vcard(&sig)))
+}
+
+fn find_signature(state: &AppState, id: Uuid) -> Result<EmailSignature, AppError> {
+    state
+        .signatures
+        .get(id)
+        .ok_or_else(|| AppError::NotFound(format!("Signature {} not found", id)))
+}
+
+#[derive(Debug, Serialize)]
+pub struct RenderAudit {
+    pub signature_id: Uuid,
+    pub size: SizeReport,
+    pub accessibility: Vec<ValidationError>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RenderOptions {
+    #[serde(default)]
+    pub vcard: VcardEmbed,
+}
+
+// how a rendered signature links to its vCard
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VcardEmbed {
+    #[default]
+    None,
+    // link to the vcard endpoint
+    Link,
+    // embed the vcard as a data uri so it works without access to this service
+    Inline,
+}
+
+// upload a logo, photo or banner image as the raw request body
+pub async fn upload_asset(
+    state: web::Data<AppState>,
+    query: web::Query<UploadAssetQuery>,
+    body: web::Payload,
+) -> Result<HttpResponse, AppError> {
+    let limit = state.config.assets.max_upload_bytes;
+    let content = body
+        .to_bytes_limited(limit)
+        .await
+        .map_err(|_| AppError::PayloadTooLarge(format!("Upload exceeds {} bytes", limit)))?
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+
+    let asset = match state.asset_checker.validate_upload(query.kind, &content) {
+        Ok(asset) => asset,
+        Err(errors) => {
+            return Ok(HttpResponse::UnprocessableEntity().json(serde_json::json!({
+                "errors": errors
+            })));
+        }
     };
 
-    Ok(HttpResponse::Ok().json(response))
+    info!(
+        asset_id = %asset.id,
+        kind = ?asset.kind,
+        size_bytes = asset.size_bytes,
+        "Storing asset"
+    );
+
+    state
+        .assets
+        .put(asset.clone(), content.to_vec())
+        .map_err(|e| AppError::Internal(e.to_string()))?;
+
+    Ok(HttpResponse::Created().json(asset))
+}
+
+// asset metadata
+pub async fn get_asset(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let id = path.into_inner();
+    let asset = state
+        .assets
+        .get(&id)
+        .ok_or_else(|| AppError::NotFound(format!("Asset {} not found", id)))?;
+
+    Ok(HttpResponse::Ok().json(asset))
+}
+
+// serve asset content, `/assets/<id>.<ext>`
+pub async fn serve_asset(
+    state: web::Data<AppState>,
+    path: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let file = path.into_inner();
+    let id = file.split_once('.').map_or(file.as_str(), |(id, _)| id);
+    let (asset, content) = state
+        .assets
+        .get_content(id)
+        .ok_or_else(|| AppError::NotFound(format!("Asset {} not found", file)))?;
+
+    // content addressed, so the url never changes meaning
+    Ok(HttpResponse::Ok()
+        .content_type(asset.format.content_type())
+        .insert_header((header::CACHE_CONTROL, "public, max-age=31536000, immutable"))
+        .body(content))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UploadAssetQuery {
+    pub kind: AssetKind,
+}
+
+// render a template with sample data, shown at desktop and mobile widths
+pub async fn preview_template(
+    state: web::Data<AppState>,
+    path: web::Path<Uuid>,
+    overrides: web::Query<PreviewOverrides>,
+) -> Result<HttpResponse, AppError> {
+    let template_id = path.into_inner();
+    let template = state
+        .templates
+        .get(template_id)
+        .ok_or_else(|| AppError::NotFound(format!("Template {} not found", template_id)))?;
+
+    let sample = overrides.into_inner().apply(
+        EmailSignature::builder()
+            .name("Jane Doe")
+            .email("jane.doe@example.com")
+            .phone("+1 555 010 0199")
+            .company("Example Corp")
+            .title("Senior Software Engineer")
+            .template_id(template_id),
+    );
+
+    let html = state.templates.render(template_id, &sample)?;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(crate::rendering::preview::preview_page(
+            &template.name,
+            &html,
+        )))
+}
+
+// optional query parameters replacing the sample data in a preview
+#[derive(Debug, Default, Deserialize)]
+pub struct PreviewOverrides {
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub company: Option<String>,
+    pub title: Option<String>,
+}
+
+impl PreviewOverrides {
+    fn apply(self, mut builder: EmailSignatureBuilder) -> EmailSignature {
+        if let Some(name) = self.name {
+            builder = builder.name(name);
+        }
+        if let Some(email) = self.email {
+            builder = builder.email(email);
+        }
+        if let Some(phone) = self.phone {
+            builder = builder.phone(phone);
+        }
+        if let Some(company) = self.company {
+            builder = builder.company(company);
+        }
+        if let Some(title) = self.title {
+            builder = builder.title(title);
+        }
+        builder.build()
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct BatchValidateRequest {
     pub signatures: Vec<EmailSignature>,
+    // jobs only, called once the job is finished
+    #[serde(default)]
+    pub callback_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DryRunRequest {
+    pub rules: RulesConfig,
+    // checked instead of the stored signatures when given
+    #[serde(default)]
+    pub signatures: Option<Vec<EmailSignature>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -112,11 +764,17 @@ pub struct BatchValidateResponse {
 }
 
 #[derive(Debug, Serialize)]
-pub struct BatchSummary {
-    pub total: usize,
-    pub valid: usize,
-    pub invalid: usize,
-    pub processing_time_ms: u128,
+pub struct JobResponse {
+    #[serde(flatten)]
+    pub job: Job,
+    pub summary: BatchSummary,
+}
+
+impl JobResponse {
+    fn new(job: Job) -> Self {
+        let summary = job.summary();
+        Self { job, summary }
+    }
 }
 #[cfg(test)]
 mod tests {
@@ -142,7 +800,9 @@ mod tests {
             .email("john@example.com")
             .build();
 
-        let resp = validate_signature(state, web::Json(sig)).await.unwrap();
+        let resp = validate_signature(state, web::Json(sig), None)
+            .await
+            .unwrap();
 
         assert_eq!(resp.status(), 200);
     }
@@ -154,13 +814,47 @@ mod tests {
             .email("invalid-email") // No @ symbol
             .build();
 
-        let resp = validate_signature(state, web::Json(sig)).await.unwrap();
+        let resp = validate_signature(state, web::Json(sig), None)
+            .await
+            .unwrap();
 
         assert_eq!(resp.status(), 200);
         // Note: Status is 200 because validation ran successfully
         // The ValidationResult.valid field will be false
     }
 
+    #[actix_web::test]
+    async fn test_validate_signature_localized() {
+        let state = create_test_state();
+        let app = actix_web::test::init_service(
+            actix_web::App::new()
+                .app_data(state)
+                .configure(crate::api::routes::configure),
+        )
+        .await;
+        let sig = serde_json::json!({
+            "id": Uuid::new_v4(),
+            "name": "",
+            "email": "jane@example.com",
+            "phone": null,
+            "company": null,
+            "title": null,
+            "template_id": Uuid::new_v4(),
+            "created_at": chrono::Utc::now(),
+        });
+
+        let req = actix_web::test::TestRequest::post()
+            .uri("/api/v1/signatures/validate")
+            .insert_header(("Accept-Language", "de-DE,de;q=0.9,en;q=0.5"))
+            .set_json(&sig)
+            .to_request();
+        let resp = actix_web::test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get("content-language").unwrap(), "de");
+
+        let result: serde_json::Value = actix_web::test::read_body_json(resp).await;
+        assert_eq!(result["errors"][0]["message"], "Name ist erforderlich");
+    }
+
     #[actix_web::test]
     async fn test_validate_batch() {
         let state = create_test_state();
@@ -171,8 +865,719 @@ mod tests {
             ],
         };
 
-        let resp = validate_batch(state, web::Json(request)).await.unwrap();
+        let resp = validate_batch(state, web::Json(request), None)
+            .await
+            .unwrap();
+
+        assert_eq!(resp.status(), 200);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["summary"]["valid"], 1);
+        assert_eq!(body["summary"]["invalid"], 1);
+        assert_eq!(body["summary"]["timed_out"], 0);
+    }
+
+    #[actix_web::test]
+    async fn test_validate_batch_rejected_when_full() {
+        let mut config = Config::load().unwrap();
+        config.pipeline.backpressure.max_queued_batches = 1;
+        config.pipeline.backpressure.retry_after_secs = 3;
+        let state = web::Data::new(AppState::new(config));
+        let _running = state.pipeline.admit(1, true).unwrap();
+
+        let request = BatchValidateResult {
+            signatures: vec![EmailSignature::builder().build()],
+        };
+        let err = validate_batch(state.clone(), web::Json(request), None)
+            .await
+            .unwrap_err();
+
+        let resp = actix_web::ResponseError::error_response(&err);
+        assert_eq!(resp.status(), 429);
+        assert_eq!(resp.headers().get(header::RETRY_AFTER).unwrap(), "3");
+        // single validations aren't queued behind batches
+        let sig = EmailSignature::builder().build();
+        assert!(
+            validate_signature(state, web::Json(sig), None)
+                .await
+                .is_ok()
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_submit_stored_by_pipeline() {
+        let mut config = Config::load().unwrap();
+        config.pipeline.stages = crate::pipeline::stages::STAGE_NAMES
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let state = web::Data::new(AppState::new(config));
+        let sig = EmailSignature::builder()
+            .name(" Zoe\u{0308} ")
+            .email("zoe@example.com")
+            .build();
+        let id = sig.id;
+
+        let resp = submit_signature(state.clone(), web::Json(sig), None)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 201);
+
+        // the pipeline's normalized and enriched copy, stored once
+        let stored = state.signatures.get(id).unwrap();
+        assert_eq!(stored.name, "Zo\u{00EB}");
+        assert_eq!(stored.template_id, crate::rendering::DEFAULT_TEMPLATE_ID);
+        assert_eq!(state.signatures.list().len(), 1);
+
+        // validating doesn't store
+        let other = EmailSignature::builder().email("other@example.com").build();
+        let other_id = other.id;
+        validate_signature(state.clone(), web::Json(other), None)
+            .await
+            .unwrap();
+        assert!(state.signatures.get(other_id).is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_submit_and_export_vcard() {
+        let state = create_test_state();
+        let sig = EmailSignature::builder()
+            .name("Grace Hopper")
+            .email("grace@example.com")
+            .phone("+1 555-010-0199")
+            .template_id(crate::rendering::DEFAULT_TEMPLATE_ID)
+            .build();
+        let id = sig.id;
+
+        let resp = submit_signature(state.clone(), web::Json(sig), None)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 201);
+
+        let resp = export_vcard(state.clone(), web::Path::from(id))
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("FN:Grace Hopper"));
+        assert!(body.contains("tel:+15550100199"));
+
+        let options = RenderOptions {
+            vcard: VcardEmbed::Inline,
+        };
+        let req = actix_web::test::TestRequest::default().to_http_request();
+        let resp = render_signature(req, state, web::Path::from(id), web::Query(options))
+            .await
+            .unwrap();
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("data:text/vcard;base64,"));
+    }
+
+    #[actix_web::test]
+    async fn test_submit_invalid_signature_not_stored() {
+        let state = create_test_state();
+        let sig = EmailSignature::builder().email("invalid").build();
+        let id = sig.id;
+
+        let resp = submit_signature(state.clone(), web::Json(sig), None)
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), 422);
+
+        let result = export_vcard(state, web::Path::from(id)).await;
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+    }
+
+    #[actix_web::test]
+    async fn test_upload_and_serve_asset() {
+        use actix_web::{App, test};
+
+        let state = create_test_state();
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .configure(crate::api::routes::configure),
+        )
+        .await;
+
+        // 100x100 PNG header, enough for the size checks
+        let mut png = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        png.extend_from_slice(&100u32.to_be_bytes());
+        png.extend_from_slice(&100u32.to_be_bytes());
+
+        let req = test::TestRequest::post()
+            .uri("/api/v1/assets?kind=photo")
+            .set_payload(png.clone())
+            .to_request();
+        let asset: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        let url = asset["url"].as_str().unwrap();
+        assert!(url.ends_with(".png"));
+
+        let path = url.strip_prefix("https://localhost:8080").unwrap();
+        let req = test::TestRequest::get().uri(path).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(test::read_body(resp).await, png);
+
+        // the banner rules reject a square image
+        let req = test::TestRequest::post()
+            .uri("/api/v1/assets?kind=banner")
+            .set_payload(png)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 422);
+
+        // signatures referencing the uploaded photo pass, unknown assets don't
+        let sig = EmailSignature::builder().photo_url(url).build();
+        let result = state.pipeline.process_single(sig).await;
+        assert!(result.valid, "{:?}", result.errors);
+
+        let sig = EmailSignature::builder()
+            .photo_url("https://localhost:8080/assets/missing.png")
+            .build();
+        let result = state.pipeline.process_single(sig).await;
+        assert!(matches!(result.errors[0].code, ErrorCode::UnknownAsset));
+    }
+
+    #[actix_web::test]
+    async fn test_render_over_tenant_budget() {
+        use sha2::{Digest, Sha256};
+
+        let mut config = Config::load().unwrap();
+        config
+            .tenants
+            .api_keys
+            .insert(hex::encode(Sha256::digest(b"tiny-key")), "tiny".to_string());
+        config.rendering.tenant_budgets.insert(
+            "tiny".to_string(),
+            crate::rendering::SizeBudget {
+                html_warn_bytes: 10,
+                html_max_bytes: 20,
+                mime_warn_bytes: 1000,
+                mime_max_bytes: 2000,
+            },
+        );
+        let state = web::Data::new(AppState::new(config));
+        let sig = EmailSignature::builder()
+            .template_id(crate::rendering::DEFAULT_TEMPLATE_ID)
+            .build();
+        let id = sig.id;
+        state.signatures.put(sig);
+
+        let req = actix_web::test::TestRequest::default().to_http_request();
+        let resp = render_signature(
+            req,
+            state.clone(),
+            web::Path::from(id),
+            web::Query(RenderOptions::default()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), 200);
+
+        // a tenant is only taken from an api key
+        let req = actix_web::test::TestRequest::default()
+            .insert_header(("X-Tenant-Id", "tiny"))
+            .to_http_request();
+        let resp = render_signature(
+            req,
+            state.clone(),
+            web::Path::from(id),
+            web::Query(RenderOptions::default()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), 200);
+
+        let req = actix_web::test::TestRequest::default()
+            .insert_header((header::AUTHORIZATION, "Bearer guessed"))
+            .to_http_request();
+        let err = render_signature(
+            req,
+            state.clone(),
+            web::Path::from(id),
+            web::Query(RenderOptions::default()),
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(err, AppError::Unauthorized(_)));
+
+        let req = actix_web::test::TestRequest::default()
+            .insert_header((header::AUTHORIZATION, "Bearer tiny-key"))
+            .to_http_request();
+        let resp = render_signature(
+            req,
+            state,
+            web::Path::from(id),
+            web::Query(RenderOptions::default()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), 422);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let report: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(report["status"], "exceeded");
+        assert_eq!(report["findings"][0]["code"], "TooLarge");
+    }
+
+    #[actix_web::test]
+    async fn test_size_counts_inlined_images_only() {
+        let state = create_test_state();
+        let base = state.config.assets.public_base_url.trim_end_matches('/');
+        let asset = crate::domain::assets::Asset {
+            id: "logo1".to_string(),
+            kind: AssetKind::Logo,
+            format: crate::domain::assets::ImageFormat::Png,
+            width: 100,
+            height: 100,
+            size_bytes: 20_000,
+            url: format!("{}/logo1.png", base),
+            created_at: chrono::Utc::now(),
+        };
+        state.assets.put(asset.clone(), vec![0; 20_000]).unwrap();
+
+        let template = crate::rendering::templates::SignatureTemplate {
+            id: Uuid::new_v4(),
+            name: "inline logo".to_string(),
+            html: r#"<img src="cid:logo1.png" alt="Logo"> <img src="cid:logo1.png" alt="">"#
+                .to_string(),
+        };
+        state.templates.register(template.clone()).unwrap();
+
+        // linked by url, fetched by the mail client
+        let linked = EmailSignature::builder()
+            .template_id(crate::rendering::DEFAULT_TEMPLATE_ID)
+            .photo_url(asset.url.clone())
+            .build();
+        let inlined = EmailSignature::builder().template_id(template.id).build();
+        let mut attached = Vec::new();
+        for sig in [linked, inlined] {
+            let id = sig.id;
+            state.signatures.put(sig);
+            let req = actix_web::test::TestRequest::default().to_http_request();
+            let resp = signature_size(req, state.clone(), web::Path::from(id))
+                .await
+                .unwrap();
+            let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+            let report: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            attached.push(report["breakdown"]["attached_image_bytes"].clone());
+        }
+        assert_eq!(attached, vec![0, 20_000]);
+    }
+
+    #[actix_web::test]
+    async fn test_render_multiline_low_contrast_text() {
+        let state = create_test_state();
+        let template = crate::rendering::templates::SignatureTemplate {
+            id: Uuid::new_v4(),
+            name: "faint".to_string(),
+            html:
+                "<p style=\"color: #eeeeee; background: #ffffff\">first line\r\n\tsecond line</p>"
+                    .to_string(),
+        };
+        state.templates.register(template.clone()).unwrap();
+        let sig = EmailSignature::builder().template_id(template.id).build();
+        let id = sig.id;
+        state.signatures.put(sig);
+
+        let req = actix_web::test::TestRequest::default().to_http_request();
+        let resp = render_signature(
+            req,
+            state,
+            web::Path::from(id),
+            web::Query(RenderOptions::default()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.headers().get(ACCESSIBILITY_HEADER).unwrap(), "1");
+        let warning = resp
+            .headers()
+            .get(header::WARNING)
+            .unwrap()
+            .to_str()
+            .unwrap();
+        assert!(warning.contains("first line   second line"), "{}", warning);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("second line"));
+    }
+
+    #[actix_web::test]
+    async fn test_audit_signature() {
+        let state = create_test_state();
+        let sig = EmailSignature::builder()
+            .template_id(crate::rendering::DEFAULT_TEMPLATE_ID)
+            .banner(Banner {
+                image_url: "https://cdn.example.com/banner.png".to_string(),
+                link_url: Some("https://example.com/sale".to_string()),
+                alt_text: None,
+            })
+            .build();
+        let id = sig.id;
+        state.signatures.put(sig);
 
+        // rendering reports the same findings without refusing
+        let req = actix_web::test::TestRequest::default().to_http_request();
+        let resp = render_signature(
+            req,
+            state.clone(),
+            web::Path::from(id),
+            web::Query(RenderOptions::default()),
+        )
+        .await
+        .unwrap();
         assert_eq!(resp.status(), 200);
+        assert_eq!(resp.headers().get(ACCESSIBILITY_HEADER).unwrap(), "2");
+        assert_eq!(resp.headers().get_all(header::WARNING).count(), 2);
+
+        let req = actix_web::test::TestRequest::default().to_http_request();
+        let resp = audit_signature(req, state, web::Path::from(id), None)
+            .await
+            .unwrap();
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let audit: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(audit["size"]["status"], "ok");
+        // a linked banner without alt text leaves the link unnamed
+        let codes: Vec<&str> = audit["accessibility"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| f["code"].as_str().unwrap())
+            .collect();
+        assert_eq!(codes, vec!["MissingAltText", "AmbiguousLinkText"]);
+    }
+
+    #[actix_web::test]
+    async fn test_preview_template() {
+        let state = create_test_state();
+        let overrides = PreviewOverrides {
+            name: Some("Ada Lovelace".to_string()),
+            ..Default::default()
+        };
+
+        let resp = preview_template(
+            state,
+            web::Path::from(crate::rendering::DEFAULT_TEMPLATE_ID),
+            web::Query(overrides),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(resp.status(), 200);
+        let body = actix_web::body::to_bytes(resp.into_body()).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("Ada Lovelace"));
+        assert!(body.contains("Mobile"));
+    }
+
+    #[actix_web::test]
+    async fn test_preview_unknown_template() {
+        let state = create_test_state();
+
+        let result = preview_template(
+            state,
+            web::Path::from(Uuid::new_v4()),
+            web::Query(PreviewOverrides::default()),
+        )
+        .await;
+
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+    }
+
+    #[actix_web::test]
+    async fn test_job_lifecycle() {
+        use actix_web::{App, test};
+
+        let state = create_test_state();
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .configure(crate::api::routes::configure),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/v1/jobs")
+            .set_json(serde_json::json!({
+                "signatures": [
+                    EmailSignature::builder().email("valid@example.com").build(),
+                    EmailSignature::builder().email("invalid").build(),
+                ]
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 202);
+        let job: serde_json::Value = test::read_body_json(resp).await;
+        let uri = format!("/api/v1/jobs/{}", job["id"].as_str().unwrap());
+
+        let mut job = serde_json::Value::Null;
+        for _ in 0..100 {
+            let req = test::TestRequest::get().uri(&uri).to_request();
+            job = test::call_and_read_body_json(&app, req).await;
+            if job["status"] == "completed" {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert_eq!(job["status"], "completed");
+        assert_eq!(job["results"].as_array().unwrap().len(), 2);
+        assert_eq!(job["summary"]["valid"], 1);
+        assert_eq!(job["summary"]["invalid"], 1);
+
+        // a finished job's event stream is just its summary
+        let req = test::TestRequest::get()
+            .uri(&format!("{}/events", uri))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/event-stream"
+        );
+        let body = test::read_body(resp).await;
+        let body = std::str::from_utf8(&body).unwrap();
+        let data = body
+            .strip_prefix("event: summary\ndata: ")
+            .and_then(|rest| rest.strip_suffix("\n\n"))
+            .unwrap();
+        let summary: serde_json::Value = serde_json::from_str(data).unwrap();
+        assert_eq!(summary["status"], "completed");
+        assert_eq!(summary["summary"]["valid"], 1);
+
+        // cancelling a finished job leaves it completed
+        let req = test::TestRequest::delete().uri(&uri).to_request();
+        let job: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(job["status"], "completed");
+
+        let req = test::TestRequest::delete()
+            .uri(&format!("/api/v1/jobs/{}", Uuid::new_v4()))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+
+        let req = test::TestRequest::post()
+            .uri("/api/v1/jobs")
+            .set_json(serde_json::json!({
+                "signatures": [EmailSignature::builder().build()],
+                "callback_url": "ftp://example.com/hook"
+            }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_revalidation_report() {
+        use actix_web::{App, test};
+
+        let state = create_test_state();
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .configure(crate::api::routes::configure),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/revalidation/report")
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+
+        state
+            .signatures
+            .put(EmailSignature::builder().email("invalid").build());
+        let req = test::TestRequest::post()
+            .uri("/api/v1/revalidation/run")
+            .to_request();
+        let report: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(report["checked"], 1);
+        assert_eq!(report["newly_failing"][0]["email"], "invalid");
+        assert_eq!(report["newly_failing"][0]["valid"], false);
+
+        let req = test::TestRequest::get()
+            .uri("/api/v1/revalidation/report")
+            .to_request();
+        let latest: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(latest["finished_at"], report["finished_at"]);
+    }
+
+    #[actix_web::test]
+    async fn test_policy_dry_run() {
+        use actix_web::{App, test};
+
+        let state = create_test_state();
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .configure(crate::api::routes::configure),
+        )
+        .await;
+
+        let mismatched = EmailSignature::builder()
+            .name("Jane Doe")
+            .email("xy@example.com")
+            .build();
+        state.signatures.put(mismatched.clone());
+        state.signatures.put(
+            EmailSignature::builder()
+                .name("Jane Doe")
+                .email("jane@example.com")
+                .build(),
+        );
+        let rules_version = state.pipeline.rules_version();
+
+        let req = test::TestRequest::post()
+            .uri("/api/v1/policies/dry-run")
+            .set_json(serde_json::json!({ "rules": { "name_matches_email": true } }))
+            .to_request();
+        let impact: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(impact["checked"], 2);
+        assert_eq!(impact["became_invalid"], 1);
+        assert_eq!(impact["unchanged_valid"], 1);
+        assert_eq!(impact["newly_failing"][0]["rule"], "name_matches_email");
+        assert_eq!(impact["newly_failing"][0]["code"], "NameMismatch");
+        assert_eq!(
+            impact["newly_failing"][0]["examples"][0],
+            mismatched.id.to_string()
+        );
+        // nothing applied
+        assert_eq!(state.pipeline.rules_version(), rules_version);
+        assert_eq!(state.signatures.list().len(), 2);
+
+        // a sample instead of the stored signatures
+        let req = test::TestRequest::post()
+            .uri("/api/v1/policies/dry-run")
+            .set_json(serde_json::json!({
+                "rules": {},
+                "signatures": [EmailSignature::builder().name("Max Power").email("max@example.com").build()]
+            }))
+            .to_request();
+        let impact: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(impact["checked"], 1);
+        assert_eq!(impact["unchanged_valid"], 1);
+        assert_eq!(state.signatures.list().len(), 2);
+
+        let req = test::TestRequest::post()
+            .uri("/api/v1/policies/dry-run")
+            .set_json(serde_json::json!({ "rules": { "field_rules": [{
+                "name": "broken",
+                "when_field": "company",
+                "when_matches": "(",
+                "then_field": "title"
+            }] } }))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn test_wrong_method_not_allowed() {
+        use crate::error::Problem;
+        use actix_web::{App, test};
+
+        let app = test::init_service(
+            App::new()
+                .app_data(create_test_state())
+                .configure(crate::api::routes::configure),
+        )
+        .await;
+
+        let job = format!("/api/v1/jobs/{}", Uuid::new_v4());
+        for (method, uri, allow) in [
+            (Method::PUT, "/api/v1/signatures/validate", "POST"),
+            (Method::DELETE, "/api/v1/policies/dry-run", "POST"),
+            (Method::POST, "/health", "GET"),
+            (Method::PATCH, job.as_str(), "GET, DELETE"),
+        ] {
+            let req = test::TestRequest::default()
+                .method(method)
+                .uri(uri)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), 405, "{}", uri);
+            assert_eq!(resp.headers().get(header::ALLOW).unwrap(), allow);
+            let problem: Problem = test::read_body_json(resp).await;
+            assert_eq!(problem.code, "method_not_allowed");
+        }
+
+        // paths that don't exist at all are still not found
+        let req = test::TestRequest::put()
+            .uri("/api/v1/signatures/nowhere/else")
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn test_errors_are_problems() {
+        use crate::api::request_id::{REQUEST_ID_HEADER, RequestId};
+        use crate::error::Problem;
+        use actix_web::{App, test};
+
+        let app = test::init_service(
+            App::new()
+                .app_data(create_test_state())
+                .wrap(RequestId)
+                .configure(crate::api::routes::configure),
+        )
+        .await;
+
+        async fn problem(
+            resp: actix_web::dev::ServiceResponse<impl actix_web::body::MessageBody>,
+        ) -> Problem {
+            assert_eq!(
+                resp.headers().get(header::CONTENT_TYPE).unwrap(),
+                "application/problem+json"
+            );
+            let request_id = resp.headers().get(REQUEST_ID_HEADER).unwrap().clone();
+            let problem: Problem = test::read_body_json(resp).await;
+            assert_eq!(problem.request_id.as_deref(), request_id.to_str().ok());
+            problem
+        }
+
+        let req = test::TestRequest::post()
+            .uri("/api/v1/signatures/validate")
+            .insert_header((header::CONTENT_TYPE, "application/json"))
+            .set_payload("{\"name\": ")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+        let malformed = problem(resp).await;
+        assert_eq!(malformed.status, 400);
+        assert_eq!(malformed.code, "invalid_request");
+        assert_eq!(
+            malformed.problem_type,
+            "urn:email-processor:problem:invalid_request"
+        );
+
+        let req = test::TestRequest::post()
+            .uri("/api/v1/signatures/validate")
+            .insert_header((header::CONTENT_TYPE, "application/json"))
+            .set_payload(vec![b' '; 2 * 1024 * 1024 + 1])
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 413);
+        assert_eq!(problem(resp).await.code, "payload_too_large");
+
+        let req = test::TestRequest::post()
+            .uri("/api/v1/signatures/validate")
+            .insert_header((header::CONTENT_TYPE, "text/plain"))
+            .set_payload("{}")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 415);
+        assert_eq!(problem(resp).await.code, "unsupported_media_type");
+
+        // a client's own request id is kept
+        let req = test::TestRequest::get()
+            .uri("/api/v1/jobs/not-a-uuid")
+            .insert_header((REQUEST_ID_HEADER, "client-42"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 400);
+        assert_eq!(problem(resp).await.request_id.as_deref(), Some("client-42"));
+
+        let req = test::TestRequest::get().uri("/api/v2/nothing").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), 404);
+        assert_eq!(problem(resp).await.code, "not_found");
     }
 }
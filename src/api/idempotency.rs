@@ -0,0 +1,373 @@
+use actix_web::{
+    Error, HttpMessage, HttpResponse,
+    body::{BoxBody, MessageBody, to_bytes},
+    dev::{Payload, Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
+    http::{Method, StatusCode},
+    web::{self, Bytes, BytesMut},
+};
+use futures_util::{StreamExt, future::LocalBoxFuture};
+use sha2::{Digest, Sha256};
+use std::cell::Cell;
+use std::future::{Ready, ready};
+use std::rc::Rc;
+
+use super::AppState;
+use crate::error::AppError;
+use crate::infrastructure::idempotency::{CachedResponse, Fingerprint};
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+// set on responses answered from the cache
+pub const REPLAYED_HEADER: &str = "Idempotent-Replayed";
+const MAX_KEY_LEN: usize = 255;
+// json bodies are capped lower by the extractor, uploads by max_upload_bytes
+const MAX_JSON_BYTES: usize = 2 * 1024 * 1024;
+
+// answers a retried POST carrying the same `Idempotency-Key` with the response to the first one,
+// so the pipeline and its metrics only run once
+pub struct Idempotency;
+
+impl<S, B> Transform<S, ServiceRequest> for Idempotency
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = IdempotencyMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(IdempotencyMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct IdempotencyMiddleware<S> {
+    service: Rc<S>,
+}
+
+// why a response wasn't kept
+enum NotCached {
+    Failed(Error),
+    // the same request may well succeed when retried
+    Retryable(CachedResponse),
+}
+
+impl<S, B> Service<ServiceRequest> for IdempotencyMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+
+        Box::pin(async move {
+            let key = match idempotency_key(&req) {
+                Ok(Some(key)) if req.method() == Method::POST => key,
+                Ok(_) => return service.call(req).await.map(|res| res.map_into_boxed_body()),
+                Err(e) => return Ok(req.error_response(e)),
+            };
+            let Some(state) = req.app_data::<web::Data<AppState>>().cloned() else {
+                return service.call(req).await.map(|res| res.map_into_boxed_body());
+            };
+
+            let limit = MAX_JSON_BYTES.max(state.config.assets.max_upload_bytes);
+            let body = match read_body(&mut req, limit).await {
+                Ok(body) => body,
+                Err(e) => return Ok(req.error_response(e)),
+            };
+            let Some(slot) = state.idempotency.slot(&key, fingerprint(&req, &body)) else {
+                return Ok(req.error_response(AppError::IdempotencyKeyReused(format!(
+                    "{} was already used for a different request",
+                    IDEMPOTENCY_KEY_HEADER
+                ))));
+            };
+            // handed back for the extractors downstream
+            req.set_payload(Payload::from(body));
+
+            // the request is only handed to the service if this call fills the slot
+            let pending = Cell::new(Some(req));
+            let served = Cell::new(None);
+
+            let outcome = slot
+                .get_or_try_init(|| async {
+                    let req = pending.take().expect("slot filled once");
+                    let (http_req, res) = service
+                        .call(req)
+                        .await
+                        .map_err(NotCached::Failed)?
+                        .into_parts();
+                    served.set(Some(http_req));
+
+                    let cached = buffer(res).await.map_err(NotCached::Failed)?;
+                    if retryable(cached.status) {
+                        Err(NotCached::Retryable(cached))
+                    } else {
+                        Ok(cached)
+                    }
+                })
+                .await;
+
+            let uncached;
+            let cached = match outcome {
+                Ok(cached) => cached,
+                Err(NotCached::Retryable(response)) => {
+                    uncached = response;
+                    &uncached
+                }
+                Err(NotCached::Failed(e)) => return Err(e),
+            };
+
+            let (http_req, replayed) = match pending.take() {
+                Some(req) => (req.into_parts().0, true),
+                None => (served.take().expect("request served"), false),
+            };
+
+            Ok(respond(http_req, cached, replayed))
+        })
+    }
+}
+
+// server errors, throttling, timeouts and conflicts describe the moment, not the request,
+// replaying them would keep the client from ever getting through with its key
+fn retryable(status: StatusCode) -> bool {
+    status.is_server_error()
+        || matches!(
+            status,
+            StatusCode::TOO_MANY_REQUESTS | StatusCode::REQUEST_TIMEOUT | StatusCode::CONFLICT
+        )
+}
+
+fn idempotency_key(req: &ServiceRequest) -> Result<Option<String>, AppError> {
+    let Some(value) = req.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(None);
+    };
+
+    match value.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LEN => Ok(Some(key.to_string())),
+        _ => Err(AppError::Validation(format!(
+            "{} must be 1 to {} visible ASCII characters",
+            IDEMPOTENCY_KEY_HEADER, MAX_KEY_LEN
+        ))),
+    }
+}
+
+async fn read_body(req: &mut ServiceRequest, limit: usize) -> Result<Bytes, AppError> {
+    let mut payload = req.take_payload();
+    let mut body = BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(|e| AppError::Validation(e.to_string()))?;
+        if body.len() + chunk.len() > limit {
+            return Err(AppError::PayloadTooLarge(format!(
+                "Request body exceeds {} bytes",
+                limit
+            )));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body.freeze())
+}
+
+// a key stands for one request, the same method, path and body each time
+fn fingerprint(req: &ServiceRequest, body: &[u8]) -> Fingerprint {
+    let mut hasher = Sha256::new();
+    hasher.update(req.method().as_str());
+    hasher.update(b" ");
+    hasher.update(req.path());
+    hasher.update(b"\n");
+    hasher.update(body);
+    hasher.finalize().into()
+}
+
+async fn buffer<B: MessageBody>(res: HttpResponse<B>) -> Result<CachedResponse, Error> {
+    let status = res.status();
+    let headers = res.headers().clone();
+    let body = to_bytes(res.into_body()).await.map_err(|e| {
+        let e: Box<dyn std::error::Error> = e.into();
+        Error::from(AppError::Internal(e.to_string()))
+    })?;
+
+    Ok(CachedResponse {
+        status,
+        headers,
+        body,
+    })
+}
+
+fn respond(
+    req: actix_web::HttpRequest,
+    cached: &CachedResponse,
+    replayed: bool,
+) -> ServiceResponse<BoxBody> {
+    let mut response = HttpResponse::build(cached.status);
+    for (name, value) in cached.headers.iter() {
+        response.append_header((name.clone(), value.clone()));
+    }
+    if replayed {
+        response.insert_header((REPLAYED_HEADER, "true"));
+    }
+
+    ServiceResponse::new(req, response.body(cached.body.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{AppState, routes};
+    use crate::domain::EmailSignature;
+    use crate::infrastructure::Config;
+    use actix_web::{App, test};
+
+    fn validate(key: &str, sig: &EmailSignature) -> test::TestRequest {
+        test::TestRequest::post()
+            .uri("/api/v1/signatures/validate")
+            .insert_header((IDEMPOTENCY_KEY_HEADER, key))
+            .set_json(sig)
+    }
+
+    #[actix_web::test]
+    async fn test_retry_replays_response() {
+        let state = web::Data::new(AppState::new(Config::load().unwrap()));
+        let app = test::init_service(App::new().app_data(state).configure(routes::configure)).await;
+        let sig = EmailSignature::builder().build();
+
+        let first = test::call_service(&app, validate("retry-1", &sig).to_request()).await;
+        assert!(first.headers().get(REPLAYED_HEADER).is_none());
+        let first: serde_json::Value = test::read_body_json(first).await;
+
+        let retry = test::call_service(&app, validate("retry-1", &sig).to_request()).await;
+        assert_eq!(retry.headers().get(REPLAYED_HEADER).unwrap(), "true");
+        let retry: serde_json::Value = test::read_body_json(retry).await;
+        assert_eq!(retry, first);
+
+        // a new key runs the pipeline again
+        let other: serde_json::Value =
+            test::call_and_read_body_json(&app, validate("retry-2", &sig).to_request()).await;
+        assert_ne!(other["validated_at"], first["validated_at"]);
+    }
+
+    #[actix_web::test]
+    async fn test_revalidation_run_replayed() {
+        let state = web::Data::new(AppState::new(Config::load().unwrap()));
+        let app = test::init_service(App::new().app_data(state).configure(routes::configure)).await;
+        let run = || {
+            test::TestRequest::post()
+                .uri("/api/v1/revalidation/run")
+                .insert_header((IDEMPOTENCY_KEY_HEADER, "nightly-rerun"))
+                .to_request()
+        };
+
+        let first = test::call_service(&app, run()).await;
+        assert_eq!(first.status(), StatusCode::OK);
+        assert!(first.headers().get(REPLAYED_HEADER).is_none());
+
+        let retry = test::call_service(&app, run()).await;
+        assert_eq!(retry.headers().get(REPLAYED_HEADER).unwrap(), "true");
+    }
+
+    #[actix_web::test]
+    async fn test_replayed_problem_carries_new_request_id() {
+        use crate::api::request_id::{REQUEST_ID_HEADER, RequestId};
+        use crate::error::Problem;
+
+        let state = web::Data::new(AppState::new(Config::load().unwrap()));
+        let app = test::init_service(
+            App::new()
+                .app_data(state)
+                .wrap(RequestId)
+                .configure(routes::configure),
+        )
+        .await;
+        let malformed = |request_id: &str| {
+            test::TestRequest::post()
+                .uri("/api/v1/signatures/validate")
+                .insert_header((IDEMPOTENCY_KEY_HEADER, "malformed"))
+                .insert_header((REQUEST_ID_HEADER, request_id))
+                .insert_header((actix_web::http::header::CONTENT_TYPE, "application/json"))
+                .set_payload("{\"name\": ")
+                .to_request()
+        };
+
+        let first = test::call_service(&app, malformed("first")).await;
+        assert_eq!(first.status(), StatusCode::BAD_REQUEST);
+        let first: Problem = test::read_body_json(first).await;
+        assert_eq!(first.request_id.as_deref(), Some("first"));
+
+        let retry = test::call_service(&app, malformed("retry")).await;
+        assert_eq!(retry.headers().get(REPLAYED_HEADER).unwrap(), "true");
+        assert_eq!(retry.headers().get(REQUEST_ID_HEADER).unwrap(), "retry");
+        let retry: Problem = test::read_body_json(retry).await;
+        assert_eq!(retry.request_id.as_deref(), Some("retry"));
+        assert_eq!(retry.code, first.code);
+    }
+
+    #[actix_web::test]
+    async fn test_throttled_request_not_replayed() {
+        let mut config = Config::load().unwrap();
+        config.pipeline.backpressure.max_queued_batches = 1;
+        let state = web::Data::new(AppState::new(config));
+        let app = test::init_service(
+            App::new()
+                .app_data(state.clone())
+                .configure(routes::configure),
+        )
+        .await;
+        let body = serde_json::json!({ "signatures": [EmailSignature::builder().build()] });
+        let batch = || {
+            test::TestRequest::post()
+                .uri("/api/v1/signatures/validate-batch")
+                .insert_header((IDEMPOTENCY_KEY_HEADER, "throttled"))
+                .set_json(&body)
+                .to_request()
+        };
+
+        let running = state.pipeline.admit(1, true).unwrap();
+        let res = test::call_service(&app, batch()).await;
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        drop(running);
+        let res = test::call_service(&app, batch()).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.headers().get(REPLAYED_HEADER).is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_key_reused_for_other_request() {
+        let state = web::Data::new(AppState::new(Config::load().unwrap()));
+        let app = test::init_service(App::new().app_data(state).configure(routes::configure)).await;
+
+        let first = EmailSignature::builder().name("Jane Doe").build();
+        let res = test::call_service(&app, validate("reused", &first).to_request()).await;
+        assert!(res.status().is_success());
+
+        let other = EmailSignature::builder().name("John Doe").build();
+        let res = test::call_service(&app, validate("reused", &other).to_request()).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let problem: serde_json::Value = test::read_body_json(res).await;
+        assert_eq!(problem["code"], "idempotency_key_reused");
+    }
+
+    #[actix_web::test]
+    async fn test_oversized_key_rejected() {
+        let state = web::Data::new(AppState::new(Config::load().unwrap()));
+        let app = test::init_service(App::new().app_data(state).configure(routes::configure)).await;
+
+        let req = validate(
+            &"k".repeat(MAX_KEY_LEN + 1),
+            &EmailSignature::builder().build(),
+        )
+        .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+}
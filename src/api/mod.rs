@@ -1,5 +1,7 @@
 pub mod handlers;
+pub mod idempotency;
 pub mod middleware;
+pub mod request_id;
 pub mod routes;
 pub mod state;
 
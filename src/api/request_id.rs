@@ -0,0 +1,141 @@
+use actix_web::{
+    Error, HttpResponse,
+    body::{BoxBody, MessageBody, to_bytes},
+    dev::{Service, ServiceRequest, ServiceResponse, Transform, forward_ready},
+    error::InternalError,
+    http::header::{self, HeaderName, HeaderValue},
+};
+use futures_util::future::LocalBoxFuture;
+use std::future::{Ready, ready};
+use tracing::{Instrument, info_span};
+use uuid::Uuid;
+
+use crate::error::{PROBLEM_CONTENT_TYPE, Problem};
+
+// taken from the client when it sends a usable one, echoed on every response
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+// longer ids from clients are replaced rather than logged
+const MAX_LEN: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+// id of the request being handled, none outside of the RequestId middleware
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+// assigns each request an id and writes it into problem bodies, wrap it around everything
+// but compression, which has to come after the body is final
+pub struct RequestId;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestId
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequestIdMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestIdMiddleware { service }))
+    }
+}
+
+pub struct RequestIdMiddleware<S> {
+    service: S,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error>,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<BoxBody>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let id = req
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|id| usable(id))
+            .map(str::to_string)
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+        let fut = self.service.call(req);
+        let header = HeaderValue::from_str(&id).expect("request ids are visible ascii");
+        let span = info_span!("request", request_id = %id);
+
+        Box::pin(
+            REQUEST_ID
+                .scope(id.clone(), async move {
+                    match fut.await {
+                        Ok(res) => {
+                            let (req, response) = res.into_parts();
+                            let mut response = stamp(response, &id).await;
+                            response
+                                .headers_mut()
+                                .insert(HeaderName::from_static("x-request-id"), header);
+                            Ok(ServiceResponse::new(req, response))
+                        }
+                        // rendered here, while the request id is still known
+                        Err(e) => {
+                            let mut response = stamp(e.error_response(), &id).await;
+                            response
+                                .headers_mut()
+                                .insert(HeaderName::from_static("x-request-id"), header);
+                            Err(InternalError::from_response(e, response).into())
+                        }
+                    }
+                })
+                .instrument(span),
+        )
+    }
+}
+
+// problems are rendered without the request id, a replayed one carries the id of the
+// request it was first made for, so either way this request's id is written in here
+async fn stamp<B: MessageBody + 'static>(
+    response: HttpResponse<B>,
+    id: &str,
+) -> HttpResponse<BoxBody> {
+    let is_problem = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|value| {
+            value
+                .as_bytes()
+                .starts_with(PROBLEM_CONTENT_TYPE.as_bytes())
+        });
+    if !is_problem {
+        return response.map_into_boxed_body();
+    }
+
+    let (response, body) = response.into_parts();
+    let Ok(bytes) = to_bytes(body).await else {
+        return response.set_body(BoxBody::new(()));
+    };
+    let body = match serde_json::from_slice::<Problem>(&bytes) {
+        Ok(mut problem) => {
+            problem.request_id = Some(id.to_string());
+            serde_json::to_vec(&problem).map_or(bytes, Into::into)
+        }
+        Err(_) => bytes,
+    };
+    response.set_body(BoxBody::new(body))
+}
+
+fn usable(id: &str) -> bool {
+    !id.is_empty() && id.len() <= MAX_LEN && id.bytes().all(|b| b.is_ascii_graphic())
+}
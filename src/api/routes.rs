@@ -1,17 +1,94 @@
-use actix_web::web;
+use actix_web::{FromRequest, Handler, Resource, Responder, http::Method, web};
 
-use super::{handlers, middleware::Metrics};
+use super::{handlers, idempotency::Idempotency, middleware::Metrics};
+use crate::error::AppError;
 
 // Configure all API routes
 pub fn configure(cfg: &mut web::ServiceConfig) {
     cfg
+        // extractor errors are reported like any other error
+        .app_data(web::JsonConfig::default().error_handler(|e, _| AppError::from(e).into()))
+        .app_data(web::PathConfig::default().error_handler(|e, _| AppError::from(e).into()))
+        .app_data(web::QueryConfig::default().error_handler(|e, _| AppError::from(e).into()))
+        .default_service(web::to(handlers::not_found))
         // Health check
-        .route("/health", web::get().to(handlers::health))
+        .service(get("/health", handlers::health))
         // API v1 routes
         .service(
             web::scope("/api/v1/signatures")
+                .wrap(Idempotency)
                 .wrap(Metrics)
-                .route("/validate", web::post().to(handlers::validate_signature))
-                .route("/validate-batch", web::post().to(handlers::validate_batch)),
-        );
+                .service(post("", handlers::submit_signature))
+                .service(post("/validate", handlers::validate_signature))
+                .service(post("/validate-batch", handlers::validate_batch))
+                .service(get("/{id}/render", handlers::render_signature))
+                .service(get("/{id}/size", handlers::signature_size))
+                .service(get("/{id}/audit", handlers::audit_signature))
+                .service(get("/{id}/vcard", handlers::export_vcard)),
+        )
+        .service(
+            web::scope("/api/v1/jobs")
+                .wrap(Idempotency)
+                .wrap(Metrics)
+                .service(post("", handlers::create_job))
+                .service(get("/dead-letters", handlers::list_dead_letters))
+                .service(
+                    resource("/{id}", &[Method::GET, Method::DELETE])
+                        .route(web::get().to(handlers::get_job))
+                        .route(web::delete().to(handlers::cancel_job)),
+                )
+                .service(get("/{id}/events", handlers::job_events)),
+        )
+        .service(
+            web::scope("/api/v1/revalidation")
+                .wrap(Idempotency)
+                .wrap(Metrics)
+                .service(post("/run", handlers::run_revalidation))
+                .service(get("/report", handlers::revalidation_report)),
+        )
+        .service(
+            web::scope("/api/v1/policies")
+                .wrap(Idempotency)
+                .wrap(Metrics)
+                .service(post("/dry-run", handlers::policy_dry_run)),
+        )
+        .service(
+            web::scope("/api/v1/templates")
+                .wrap(Metrics)
+                .service(get("/{id}/preview", handlers::preview_template)),
+        )
+        .service(
+            web::scope("/api/v1/assets")
+                .wrap(Idempotency)
+                .wrap(Metrics)
+                .service(post("", handlers::upload_asset))
+                .service(get("/{id}", handlers::get_asset)),
+        )
+        // uploaded images, referenced from signatures
+        .service(get("/assets/{file}", handlers::serve_asset));
+}
+
+// a path taking the given methods, any other gets a 405 naming them rather than a 404
+fn resource(path: &str, allowed: &'static [Method]) -> Resource {
+    web::resource(path).default_service(web::to(move |req| {
+        handlers::method_not_allowed(req, allowed)
+    }))
+}
+
+fn get<F, Args>(path: &str, handler: F) -> Resource
+where
+    F: Handler<Args>,
+    Args: FromRequest + 'static,
+    F::Output: Responder + 'static,
+{
+    resource(path, &[Method::GET]).route(web::get().to(handler))
+}
+
+fn post<F, Args>(path: &str, handler: F) -> Resource
+where
+    F: Handler<Args>,
+    Args: FromRequest + 'static,
+    F::Output: Responder + 'static,
+{
+    resource(path, &[Method::POST]).route(web::post().to(handler))
 }
@@ -1,19 +1,194 @@
-use crate::{infrastructure::Config, pipeline::PipelineManager};
-use std::sync::Arc;
+use crate::{
+    domain::{
+        AssetChecker, AssetStore, CrossFieldRule, DirectoryHandle, LinkValidator,
+        SignatureValidator,
+    },
+    i18n::MessageCatalog,
+    infrastructure::{
+        Config, FileAssetStore, InMemoryAssetStore, InMemorySignatureStore, SignatureStore,
+        http_prober::HttpProber, idempotency::IdempotencyCache, webhook::WebhookSender,
+    },
+    pipeline::{JobQueue, LruValidationCache, PipelineManager, Revalidator},
+    rendering::TemplateRegistry,
+};
+use std::{sync::Arc, time::Duration};
+use tracing::warn;
 
 // shared app state
 // cloned for each "worker", but Arc makes it cheap because of reference counting
 #[derive(Clone)]
 pub struct AppState {
     pub pipeline: Arc<PipelineManager>,
+    pub jobs: Arc<JobQueue>,
+    pub revalidator: Arc<Revalidator>,
+    pub templates: Arc<TemplateRegistry>,
+    pub signatures: Arc<dyn SignatureStore>,
+    pub assets: Arc<dyn AssetStore>,
+    pub asset_checker: Arc<AssetChecker>,
+    pub messages: Arc<MessageCatalog>,
+    pub directory: Option<Arc<DirectoryHandle>>,
+    pub idempotency: Arc<IdempotencyCache>,
     pub config: Arc<Config>,
 }
 
 impl AppState {
     pub fn new(config: Config) -> Self {
+        let templates = TemplateRegistry::new();
+        if let Some(dir) = &config.templates.dir
+            && let Err(e) = templates.load_dir(dir)
+        {
+            warn!(dir = %dir, error = %e, "Failed to load templates");
+        }
+
+        let assets = create_asset_store(&config);
+        let asset_checker = Arc::new(AssetChecker::new(
+            assets.clone(),
+            &config.assets.public_base_url,
+            config.assets.logo.clone(),
+            config.assets.photo.clone(),
+            config.assets.banner.clone(),
+        ));
+
+        let directory = config.directory.path.as_ref().and_then(|path| {
+            DirectoryHandle::open(path)
+                .map(Arc::new)
+                .inspect_err(
+                    |e| warn!(path = %path, error = %e, "Failed to load organization directory"),
+                )
+                .ok()
+        });
+
+        let rules = config.rules.build().unwrap_or_else(|e| {
+            warn!(error = %e, "Ignoring invalid cross-field rules");
+            Vec::new()
+        });
+        let validator = create_validator(&asset_checker, directory.as_ref(), rules);
+
+        let signatures: Arc<dyn SignatureStore> = Arc::new(InMemorySignatureStore::new());
+
+        let templates = Arc::new(templates);
+
+        let mut pipeline = PipelineManager::builder()
+            .validator(validator)
+            .store(signatures.clone())
+            .templates(templates.clone())
+            .workers(config.pipeline.workers)
+            .queue_depth(config.pipeline.queue_depth)
+            .limits(config.pipeline.backpressure.clone())
+            .deadlines(config.pipeline.deadlines.clone())
+            .stages(&config.pipeline.stages);
+        if config.pipeline.cache.enabled {
+            pipeline = pipeline.cache(Arc::new(LruValidationCache::new(
+                config.pipeline.cache.max_entries,
+                Duration::from_secs(config.pipeline.cache.ttl_secs),
+            )));
+        }
+        if config.links.probe {
+            match HttpProber::new(Duration::from_millis(config.links.probe_timeout_ms)) {
+                Ok(prober) => pipeline = pipeline.link_validator(LinkValidator::new(prober)),
+                Err(e) => warn!(error = %e, "Failed to create link prober, link probing disabled"),
+            }
+        }
+        let pipeline = pipeline.build().unwrap_or_else(|e| {
+            warn!(error = %e, "Invalid pipeline stages, using the defaults");
+            PipelineManager::new()
+        });
+        let pipeline = Arc::new(pipeline);
+
         Self {
-            pipeline: Arc::new(PipelineManager::new()),
+            jobs: Arc::new(create_job_queue(&config, pipeline.clone())),
+            revalidator: Arc::new(create_revalidator(
+                &config,
+                pipeline.clone(),
+                signatures.clone(),
+            )),
+            pipeline,
+            templates,
+            signatures,
+            assets,
+            asset_checker,
+            messages: Arc::new(MessageCatalog::builtin()),
+            directory,
+            idempotency: Arc::new(IdempotencyCache::new(
+                Duration::from_secs(config.idempotency.ttl_secs),
+                config.idempotency.max_entries,
+            )),
             config: Arc::new(config),
         }
     }
+
+    // a validator like the pipeline's, checking the given cross-field rules instead
+    pub fn validator_with_rules(&self, rules: Vec<Arc<dyn CrossFieldRule>>) -> SignatureValidator {
+        create_validator(&self.asset_checker, self.directory.as_ref(), rules)
+    }
+}
+
+fn create_validator(
+    asset_checker: &Arc<AssetChecker>,
+    directory: Option<&Arc<DirectoryHandle>>,
+    rules: Vec<Arc<dyn CrossFieldRule>>,
+) -> SignatureValidator {
+    let mut validator = SignatureValidator::new().with_assets(asset_checker.clone());
+    if let Some(directory) = directory {
+        validator = validator.with_directory(directory.clone());
+    }
+
+    rules
+        .into_iter()
+        .fold(validator, SignatureValidator::with_rule)
+}
+
+fn create_job_queue(config: &Config, pipeline: Arc<PipelineManager>) -> JobQueue {
+    let settings = config.pipeline.jobs.clone();
+    let mut queue = None;
+    if let Some(path) = &settings.journal {
+        match JobQueue::open(pipeline.clone(), settings.clone(), path) {
+            Ok(opened) => queue = Some(opened),
+            Err(e) => {
+                warn!(path = %path, error = %e, "Failed to open job journal, keeping jobs in memory")
+            }
+        }
+    }
+    let queue = queue.unwrap_or_else(|| JobQueue::new(pipeline, settings));
+
+    match WebhookSender::new(&config.webhooks) {
+        Ok(sender) => {
+            let base = config.webhooks.public_base_url.clone().unwrap_or_default();
+            queue.with_webhooks(sender, base.trim_end_matches('/'))
+        }
+        Err(e) => {
+            warn!(error = %e, "Failed to create webhook client, job callbacks disabled");
+            queue
+        }
+    }
+}
+
+fn create_revalidator(
+    config: &Config,
+    pipeline: Arc<PipelineManager>,
+    signatures: Arc<dyn SignatureStore>,
+) -> Revalidator {
+    let chunk_size = config.revalidation.chunk_size;
+    if let Some(path) = &config.revalidation.state {
+        match Revalidator::open(pipeline.clone(), signatures.clone(), chunk_size, path) {
+            Ok(revalidator) => return revalidator,
+            Err(e) => {
+                warn!(path = %path, error = %e, "Failed to load revalidation state, keeping statuses in memory")
+            }
+        }
+    }
+    Revalidator::new(pipeline, signatures, chunk_size)
+}
+
+fn create_asset_store(config: &Config) -> Arc<dyn AssetStore> {
+    if let Some(dir) = &config.assets.dir {
+        match FileAssetStore::open(dir) {
+            Ok(store) => return Arc::new(store),
+            Err(e) => {
+                warn!(dir = %dir, error = %e, "Failed to open asset directory, keeping assets in memory")
+            }
+        }
+    }
+
+    Arc::new(InMemoryAssetStore::new())
 }
@@ -0,0 +1,444 @@
+use chrono::{DateTime, Utc};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+use crate::domain::{EmailSignature, ErrorCode, ValidationError};
+
+static CID_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r#"(?i)\bcid:([a-z0-9._-]+)"#).unwrap());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AssetKind {
+    Logo,
+    Photo,
+    Banner,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Gif,
+}
+
+impl ImageFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::Gif => "gif",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Jpeg => "image/jpeg",
+            Self::Gif => "image/gif",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageInfo {
+    pub format: ImageFormat,
+    pub width: u32,
+    pub height: u32,
+}
+
+// an uploaded image, addressed by the sha256 of its content
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Asset {
+    pub id: String,
+    pub kind: AssetKind,
+    pub format: ImageFormat,
+    pub width: u32,
+    pub height: u32,
+    pub size_bytes: usize,
+    pub url: String,
+    pub created_at: DateTime<Utc>,
+}
+
+// limits for one kind of asset
+#[derive(Debug, Clone, Deserialize)]
+pub struct AssetRules {
+    pub max_bytes: usize,
+    pub min_width: u32,
+    pub max_width: u32,
+    pub min_height: u32,
+    pub max_height: u32,
+    // width / height
+    pub min_aspect_ratio: f64,
+    pub max_aspect_ratio: f64,
+}
+
+impl AssetRules {
+    pub fn check(&self, field: &str, info: &ImageInfo, size_bytes: usize) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+
+        if size_bytes > self.max_bytes {
+            errors.push(
+                ValidationError::new(
+                    field,
+                    format!(
+                        "Image is {} bytes (max {} bytes)",
+                        size_bytes, self.max_bytes
+                    ),
+                    ErrorCode::TooLarge,
+                )
+                .with_param("size", size_bytes)
+                .with_param("max", self.max_bytes),
+            );
+        }
+
+        if !(self.min_width..=self.max_width).contains(&info.width)
+            || !(self.min_height..=self.max_height).contains(&info.height)
+        {
+            errors.push(
+                ValidationError::new(
+                    field,
+                    format!(
+                        "Image is {}x{} (allowed {}-{} x {}-{})",
+                        info.width,
+                        info.height,
+                        self.min_width,
+                        self.max_width,
+                        self.min_height,
+                        self.max_height
+                    ),
+                    ErrorCode::InvalidDimensions,
+                )
+                .with_param("width", info.width)
+                .with_param("height", info.height)
+                .with_param("min_width", self.min_width)
+                .with_param("max_width", self.max_width)
+                .with_param("min_height", self.min_height)
+                .with_param("max_height", self.max_height),
+            );
+        }
+
+        let ratio = info.width as f64 / info.height.max(1) as f64;
+        if ratio < self.min_aspect_ratio || ratio > self.max_aspect_ratio {
+            errors.push(
+                ValidationError::new(
+                    field,
+                    format!(
+                        "Image aspect ratio {:.2} outside {:.2}-{:.2}",
+                        ratio, self.min_aspect_ratio, self.max_aspect_ratio
+                    ),
+                    ErrorCode::InvalidAspectRatio,
+                )
+                .with_param("ratio", format!("{:.2}", ratio))
+                .with_param("min_ratio", format!("{:.2}", self.min_aspect_ratio))
+                .with_param("max_ratio", format!("{:.2}", self.max_aspect_ratio)),
+            );
+        }
+
+        errors
+    }
+}
+
+// read format and dimensions from the image header, None if it isn't a supported image
+pub fn inspect_image(bytes: &[u8]) -> Option<ImageInfo> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        // IHDR is always the first chunk
+        if bytes.get(12..16)? != b"IHDR" {
+            return None;
+        }
+        return Some(ImageInfo {
+            format: ImageFormat::Png,
+            width: u32::from_be_bytes(bytes.get(16..20)?.try_into().ok()?),
+            height: u32::from_be_bytes(bytes.get(20..24)?.try_into().ok()?),
+        });
+    }
+
+    if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        return Some(ImageInfo {
+            format: ImageFormat::Gif,
+            width: u16::from_le_bytes(bytes.get(6..8)?.try_into().ok()?) as u32,
+            height: u16::from_le_bytes(bytes.get(8..10)?.try_into().ok()?) as u32,
+        });
+    }
+
+    if bytes.starts_with(&[0xFF, 0xD8]) {
+        return inspect_jpeg(bytes);
+    }
+
+    None
+}
+
+// walk the jpeg segments until a start-of-frame marker carrying the dimensions
+fn inspect_jpeg(bytes: &[u8]) -> Option<ImageInfo> {
+    let mut pos = 2;
+
+    loop {
+        // markers may be preceded by any number of 0xFF fill bytes
+        while *bytes.get(pos)? == 0xFF {
+            pos += 1;
+        }
+        let marker = *bytes.get(pos)?;
+        pos += 1;
+
+        // standalone markers carry no length
+        if marker == 0x01 || (0xD0..=0xD7).contains(&marker) {
+            continue;
+        }
+
+        let length = u16::from_be_bytes(bytes.get(pos..pos + 2)?.try_into().ok()?) as usize;
+        let is_frame = matches!(marker, 0xC0..=0xCF) && !matches!(marker, 0xC4 | 0xC8 | 0xCC);
+
+        if is_frame {
+            let height = u16::from_be_bytes(bytes.get(pos + 3..pos + 5)?.try_into().ok()?);
+            let width = u16::from_be_bytes(bytes.get(pos + 5..pos + 7)?.try_into().ok()?);
+            return Some(ImageInfo {
+                format: ImageFormat::Jpeg,
+                width: width as u32,
+                height: height as u32,
+            });
+        }
+
+        if marker == 0xDA || length < 2 {
+            // start of scan without a frame header
+            return None;
+        }
+        pos += length;
+    }
+}
+
+// storage for uploaded assets
+pub trait AssetStore: Send + Sync {
+    fn get(&self, id: &str) -> Option<Asset>;
+    fn get_content(&self, id: &str) -> Option<(Asset, Vec<u8>)>;
+    fn put(&self, asset: Asset, content: Vec<u8>) -> anyhow::Result<()>;
+}
+
+// checks images referenced by a signature against the rules for the field they're used in
+pub struct AssetChecker {
+    store: Arc<dyn AssetStore>,
+    base_url: String,
+    logo: AssetRules,
+    photo: AssetRules,
+    banner: AssetRules,
+}
+
+impl AssetChecker {
+    pub fn new(
+        store: Arc<dyn AssetStore>,
+        base_url: impl Into<String>,
+        logo: AssetRules,
+        photo: AssetRules,
+        banner: AssetRules,
+    ) -> Self {
+        Self {
+            store,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            logo,
+            photo,
+            banner,
+        }
+    }
+
+    pub fn rules(&self, kind: AssetKind) -> &AssetRules {
+        match kind {
+            AssetKind::Logo => &self.logo,
+            AssetKind::Photo => &self.photo,
+            AssetKind::Banner => &self.banner,
+        }
+    }
+
+    pub fn url_for(&self, id: &str, format: ImageFormat) -> String {
+        format!("{}/{}.{}", self.base_url, id, format.extension())
+    }
+
+    // validate an uploaded image, returning the asset to store
+    pub fn validate_upload(
+        &self,
+        kind: AssetKind,
+        content: &[u8],
+    ) -> Result<Asset, Vec<ValidationError>> {
+        let Some(info) = inspect_image(content) else {
+            return Err(vec![ValidationError::new(
+                "file",
+                "Unsupported image format (expected PNG, JPEG or GIF)",
+                ErrorCode::UnsupportedFormat,
+            )]);
+        };
+
+        let errors = self.rules(kind).check("file", &info, content.len());
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let id = hex::encode(Sha256::digest(content));
+        Ok(Asset {
+            url: self.url_for(&id, info.format),
+            id,
+            kind,
+            format: info.format,
+            width: info.width,
+            height: info.height,
+            size_bytes: content.len(),
+            created_at: Utc::now(),
+        })
+    }
+
+    // uploaded assets the html embeds as inline MIME parts, referenced as `cid:<asset id>`
+    // images linked by url are fetched by the mail client and aren't part of the message
+    pub fn inlined_assets(&self, html: &str) -> Vec<Asset> {
+        let mut ids: Vec<&str> = CID_REGEX
+            .captures_iter(html)
+            .filter_map(|c| c.get(1))
+            .map(|m| m.as_str().split_once('.').map_or(m.as_str(), |(id, _)| id))
+            .collect();
+        // one part per image, however often it's shown
+        ids.sort_unstable();
+        ids.dedup();
+
+        ids.into_iter()
+            .filter_map(|id| self.store.get(id))
+            .collect()
+    }
+
+    pub fn check(
+        &self,
+        sig: &EmailSignature,
+        errors: &mut Vec<ValidationError>,
+        warnings: &mut Vec<String>,
+    ) {
+        let images = [
+            ("logo_url", sig.logo_url.as_deref(), AssetKind::Logo),
+            ("photo_url", sig.photo_url.as_deref(), AssetKind::Photo),
+            (
+                "banner.image_url",
+                sig.banner.as_ref().map(|b| b.image_url.as_str()),
+                AssetKind::Banner,
+            ),
+        ];
+
+        for (field, url, kind) in images {
+            let Some(url) = url else {
+                continue;
+            };
+
+            let Some(id) = self.asset_id(url) else {
+                warnings.push(format!(
+                    "{} is not an uploaded asset, its size can't be checked",
+                    field
+                ));
+                continue;
+            };
+
+            match self.store.get(id) {
+                Some(asset) => {
+                    let info = ImageInfo {
+                        format: asset.format,
+                        width: asset.width,
+                        height: asset.height,
+                    };
+                    errors.extend(self.rules(kind).check(field, &info, asset.size_bytes));
+                }
+                None => errors.push(ValidationError::new(
+                    field,
+                    "Asset not found",
+                    ErrorCode::UnknownAsset,
+                )),
+            }
+        }
+    }
+
+    // id of an asset served by us, None for external urls
+    fn asset_id<'a>(&self, url: &'a str) -> Option<&'a str> {
+        let file = url.trim().strip_prefix(&self.base_url)?.strip_prefix('/')?;
+        Some(file.split_once('.').map_or(file, |(id, _)| id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = b"\x89PNG\r\n\x1a\n\0\0\0\x0dIHDR".to_vec();
+        bytes.extend_from_slice(&width.to_be_bytes());
+        bytes.extend_from_slice(&height.to_be_bytes());
+        bytes.extend_from_slice(&[8, 6, 0, 0, 0]);
+        bytes
+    }
+
+    #[test]
+    fn test_inspect_image() {
+        assert_eq!(
+            inspect_image(&png(300, 100)),
+            Some(ImageInfo {
+                format: ImageFormat::Png,
+                width: 300,
+                height: 100
+            })
+        );
+
+        let gif = b"GIF89a\x40\x01\xc8\x00";
+        assert_eq!(
+            inspect_image(gif),
+            Some(ImageInfo {
+                format: ImageFormat::Gif,
+                width: 320,
+                height: 200
+            })
+        );
+
+        // SOI, APP0 with a 4 byte payload, then SOF0 for 640x480
+        let jpeg = [
+            0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x06, b'J', b'F', b'I', b'F', 0xFF, 0xC0, 0x00, 0x11,
+            0x08, 0x01, 0xE0, 0x02, 0x80, 0x03,
+        ];
+        assert_eq!(
+            inspect_image(&jpeg),
+            Some(ImageInfo {
+                format: ImageFormat::Jpeg,
+                width: 640,
+                height: 480
+            })
+        );
+
+        assert_eq!(inspect_image(b"<svg></svg>"), None);
+        assert_eq!(inspect_image(b"\x89PNG\r\n\x1a\n"), None);
+    }
+
+    #[test]
+    fn test_asset_rules() {
+        let rules = AssetRules {
+            max_bytes: 1000,
+            min_width: 10,
+            max_width: 400,
+            min_height: 10,
+            max_height: 400,
+            min_aspect_ratio: 0.5,
+            max_aspect_ratio: 2.0,
+        };
+        let info = |width, height| ImageInfo {
+            format: ImageFormat::Png,
+            width,
+            height,
+        };
+
+        assert!(rules.check("photo_url", &info(200, 200), 500).is_empty());
+
+        let codes: Vec<ErrorCode> = rules
+            .check("photo_url", &info(800, 100), 5000)
+            .into_iter()
+            .map(|e| e.code)
+            .collect();
+        assert!(matches!(
+            codes.as_slice(),
+            [
+                ErrorCode::TooLarge,
+                ErrorCode::InvalidDimensions,
+                ErrorCode::InvalidAspectRatio
+            ]
+        ));
+    }
+}
@@ -0,0 +1,482 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::domain::{EmailSignature, ErrorCode, ValidationError};
+
+// below this similarity a suggestion would be more confusing than helpful
+const MIN_SUGGESTION_SCORE: f64 = 0.75;
+
+// expanded before comparing, so "Sr. Software Eng" matches "Senior Software Engineer"
+const ABBREVIATIONS: &[(&str, &str)] = &[
+    ("sr", "senior"),
+    ("snr", "senior"),
+    ("jr", "junior"),
+    ("eng", "engineer"),
+    ("engr", "engineer"),
+    ("dev", "developer"),
+    ("mgr", "manager"),
+    ("mngr", "manager"),
+    ("dir", "director"),
+    ("vp", "vice president"),
+    ("svp", "senior vice president"),
+    ("evp", "executive vice president"),
+    ("assoc", "associate"),
+    ("asst", "assistant"),
+    ("admin", "administrator"),
+    ("exec", "executive"),
+    ("ops", "operations"),
+    ("mktg", "marketing"),
+    ("corp", "corporation"),
+    ("inc", "incorporated"),
+    ("ltd", "limited"),
+    ("co", "company"),
+];
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum EntrySource {
+    Name(String),
+    WithAliases {
+        name: String,
+        #[serde(default)]
+        aliases: Vec<String>,
+    },
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct DirectorySource {
+    #[serde(default)]
+    companies: Vec<EntrySource>,
+    #[serde(default)]
+    titles: Vec<EntrySource>,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    canonical: String,
+    // normalized forms of the canonical name and its aliases
+    keys: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lookup {
+    Match(String),
+    Unknown { suggestion: Option<String> },
+}
+
+// canonical company names and job titles for the organization
+#[derive(Debug, Default)]
+pub struct OrgDirectory {
+    companies: Vec<Entry>,
+    titles: Vec<Entry>,
+}
+
+impl OrgDirectory {
+    // load from a `.json` or `.csv` file
+    //
+    // json: {"companies": ["Example Corp"], "titles": ["Engineer", {"name": "...", "aliases": ["..."]}]}
+    // csv: one `kind,name,aliases` row per entry, kind is company or title, aliases separated by `|`
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        let source = match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => serde_json::from_str(&content)?,
+            Some("csv") => parse_csv(&content)?,
+            _ => anyhow::bail!("Unsupported directory format: {}", path.display()),
+        };
+
+        Ok(Self::from_source(source))
+    }
+
+    pub fn new(companies: &[&str], titles: &[&str]) -> Self {
+        let entries = |names: &[&str]| {
+            names
+                .iter()
+                .map(|n| EntrySource::Name(n.to_string()))
+                .collect()
+        };
+
+        Self::from_source(DirectorySource {
+            companies: entries(companies),
+            titles: entries(titles),
+        })
+    }
+
+    fn from_source(source: DirectorySource) -> Self {
+        let build = |entries: Vec<EntrySource>| {
+            entries
+                .into_iter()
+                .map(|entry| {
+                    let (canonical, aliases) = match entry {
+                        EntrySource::Name(name) => (name, Vec::new()),
+                        EntrySource::WithAliases { name, aliases } => (name, aliases),
+                    };
+                    let keys = std::iter::once(&canonical)
+                        .chain(&aliases)
+                        .map(|n| match_key(n))
+                        .collect();
+                    Entry { canonical, keys }
+                })
+                .collect()
+        };
+
+        Self {
+            companies: build(source.companies),
+            titles: build(source.titles),
+        }
+    }
+
+    pub fn lookup_company(&self, value: &str) -> Option<Lookup> {
+        lookup(&self.companies, value)
+    }
+
+    pub fn lookup_title(&self, value: &str) -> Option<Lookup> {
+        lookup(&self.titles, value)
+    }
+}
+
+// None when the directory has no entries of this kind, so nothing can be checked
+fn lookup(entries: &[Entry], value: &str) -> Option<Lookup> {
+    if entries.is_empty() {
+        return None;
+    }
+
+    let key = match_key(value);
+    if let Some(entry) = entries.iter().find(|e| e.keys.contains(&key)) {
+        return Some(Lookup::Match(entry.canonical.clone()));
+    }
+
+    let key = key.as_str();
+    let suggestion = entries
+        .iter()
+        .flat_map(|e| {
+            e.keys
+                .iter()
+                .map(move |k| (e, strsim::jaro_winkler(k, key)))
+        })
+        .filter(|(_, score)| *score >= MIN_SUGGESTION_SCORE)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(e, _)| e.canonical.clone());
+
+    Some(Lookup::Unknown { suggestion })
+}
+
+// lowercase words with punctuation dropped and abbreviations expanded
+fn match_key(value: &str) -> String {
+    value
+        .split(|c: char| c.is_whitespace() || c == '.' || c == ',' || c == '-' || c == '/')
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            let w = w.to_lowercase();
+            ABBREVIATIONS
+                .iter()
+                .find(|(abbr, _)| *abbr == w)
+                .map(|(_, full)| full.to_string())
+                .unwrap_or(w)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn parse_csv(content: &str) -> anyhow::Result<DirectorySource> {
+    let mut source = DirectorySource::default();
+
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || (i == 0 && line.starts_with("kind,")) {
+            continue;
+        }
+
+        let record = split_record(line).map_err(|e| anyhow::anyhow!("Line {}: {}", i + 1, e))?;
+        if record.len() > 3 {
+            anyhow::bail!(
+                "Line {}: expected kind, name and aliases, quote values containing commas",
+                i + 1
+            );
+        }
+
+        let mut columns = record.iter().map(|c| c.trim());
+        let kind = columns.next().unwrap_or_default();
+        let name = columns.next().unwrap_or_default().to_string();
+        let aliases = columns
+            .next()
+            .map(|a| {
+                a.split('|')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if name.is_empty() {
+            anyhow::bail!("Line {}: missing name", i + 1);
+        }
+
+        let entry = EntrySource::WithAliases { name, aliases };
+        match kind {
+            "company" => source.companies.push(entry),
+            "title" => source.titles.push(entry),
+            _ => anyhow::bail!("Line {}: unknown kind \"{}\"", i + 1, kind),
+        }
+    }
+
+    Ok(source)
+}
+
+// fields of one csv line, double quotes around a field allow commas in it and "" stands for a quote
+fn split_record(line: &str) -> Result<Vec<String>, &'static str> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted => {
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    field.push('"');
+                } else {
+                    quoted = false;
+                }
+            }
+            '"' if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+
+    if quoted {
+        return Err("unterminated quoted field");
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+// the loaded directory, swapped out when the file changes
+pub struct DirectoryHandle {
+    path: PathBuf,
+    current: RwLock<(Arc<OrgDirectory>, Option<SystemTime>)>,
+    // bumped on every reload
+    generation: AtomicU64,
+}
+
+impl DirectoryHandle {
+    pub fn open(path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let path = path.into();
+        let modified = std::fs::metadata(&path)?.modified().ok();
+        let directory = OrgDirectory::load(&path)?;
+
+        Ok(Self {
+            path,
+            current: RwLock::new((Arc::new(directory), modified)),
+            generation: AtomicU64::new(0),
+        })
+    }
+
+    pub fn fixed(directory: OrgDirectory) -> Self {
+        Self {
+            path: PathBuf::new(),
+            current: RwLock::new((Arc::new(directory), None)),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    pub fn get(&self) -> Arc<OrgDirectory> {
+        self.current.read().unwrap().0.clone()
+    }
+
+    // reload if the file changed since the last load, a broken file keeps the previous version
+    pub fn reload_if_changed(&self) -> bool {
+        let modified = std::fs::metadata(&self.path)
+            .and_then(|m| m.modified())
+            .ok();
+        if modified.is_none() || modified == self.current.read().unwrap().1 {
+            return false;
+        }
+
+        match OrgDirectory::load(&self.path) {
+            Ok(directory) => {
+                *self.current.write().unwrap() = (Arc::new(directory), modified);
+                self.generation.fetch_add(1, Ordering::AcqRel);
+                info!(path = %self.path.display(), "Reloaded organization directory");
+                true
+            }
+            Err(e) => {
+                warn!(path = %self.path.display(), error = %e, "Failed to reload organization directory");
+                false
+            }
+        }
+    }
+
+    // poll the file for changes, must be called from within a tokio runtime
+    pub fn watch(self: Arc<Self>, interval: Duration) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                self.reload_if_changed();
+            }
+        });
+    }
+
+    // replace company and title with their canonical spelling where they match
+    pub fn canonicalize(&self, sig: &mut EmailSignature) {
+        let directory = self.get();
+
+        if let Some(company) = &mut sig.company
+            && let Some(Lookup::Match(canonical)) = directory.lookup_company(company)
+        {
+            *company = canonical;
+        }
+
+        if let Some(title) = &mut sig.title
+            && let Some(Lookup::Match(canonical)) = directory.lookup_title(title)
+        {
+            *title = canonical;
+        }
+    }
+
+    pub fn check(&self, sig: &EmailSignature, errors: &mut Vec<ValidationError>) {
+        let directory = self.get();
+
+        if let Some(company) = &sig.company
+            && let Some(Lookup::Unknown { suggestion }) = directory.lookup_company(company)
+        {
+            errors.push(unknown_value("company", "company", company, suggestion));
+        }
+
+        if let Some(title) = &sig.title
+            && let Some(Lookup::Unknown { suggestion }) = directory.lookup_title(title)
+        {
+            errors.push(unknown_value("title", "job title", title, suggestion));
+        }
+    }
+}
+
+fn unknown_value(
+    field: &str,
+    label: &str,
+    value: &str,
+    suggestion: Option<String>,
+) -> ValidationError {
+    let error = match suggestion {
+        Some(suggestion) => ValidationError::new(
+            field,
+            format!(
+                "Unknown {} \"{}\", did you mean \"{}\"?",
+                label, value, suggestion
+            ),
+            ErrorCode::NotInDirectory,
+        )
+        .with_param("suggestion", suggestion),
+        None => ValidationError::new(
+            field,
+            format!("Unknown {} \"{}\"", label, value),
+            ErrorCode::NotInDirectory,
+        ),
+    };
+
+    error.with_param("value", value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn directory() -> OrgDirectory {
+        OrgDirectory::new(
+            &["Example Corp"],
+            &["Senior Software Engineer", "Engineering Manager"],
+        )
+    }
+
+    #[test]
+    fn test_lookup_normalizes() {
+        let directory = directory();
+        assert_eq!(
+            directory.lookup_title("sr. software eng"),
+            Some(Lookup::Match("Senior Software Engineer".to_string()))
+        );
+        assert_eq!(
+            directory.lookup_company("EXAMPLE CORP."),
+            Some(Lookup::Match("Example Corp".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_lookup_suggests() {
+        let directory = directory();
+        assert_eq!(
+            directory.lookup_title("Senior Sofware Engineer"),
+            Some(Lookup::Unknown {
+                suggestion: Some("Senior Software Engineer".to_string())
+            })
+        );
+        assert_eq!(
+            directory.lookup_title("Astronaut"),
+            Some(Lookup::Unknown { suggestion: None })
+        );
+        assert_eq!(OrgDirectory::default().lookup_title("Astronaut"), None);
+    }
+
+    #[test]
+    fn test_load_csv_and_reload() {
+        let path = std::env::temp_dir().join(format!("directory-{}.csv", uuid::Uuid::new_v4()));
+        std::fs::write(
+            &path,
+            "kind,name,aliases\ncompany,Example Corp,ACME|Acme Inc\n",
+        )
+        .unwrap();
+
+        let handle = DirectoryHandle::open(&path).unwrap();
+        assert_eq!(
+            handle.get().lookup_company("acme"),
+            Some(Lookup::Match("Example Corp".to_string()))
+        );
+        assert_eq!(handle.get().lookup_title("Anything"), None);
+
+        // make sure the modification time moves on coarse filesystems
+        std::thread::sleep(Duration::from_millis(20));
+        std::fs::write(&path, "company,Example Corp\ntitle,Engineer\n").unwrap();
+        let file = std::fs::File::options().append(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(1))
+            .unwrap();
+
+        assert!(handle.reload_if_changed());
+        assert_eq!(handle.generation(), 1);
+        assert!(handle.get().lookup_title("eng").is_some());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_csv_quoted_fields() {
+        let source = parse_csv(
+            "kind,name,aliases\ncompany,\"Example, Inc.\",\"Example|Example, Inc\"\ntitle,\"\"\"Chief\"\" Officer\"\n",
+        )
+        .unwrap();
+        let directory = OrgDirectory::from_source(source);
+
+        assert_eq!(
+            directory.lookup_company("example inc"),
+            Some(Lookup::Match("Example, Inc.".to_string()))
+        );
+        assert_eq!(
+            directory.lookup_title("\"Chief\" Officer"),
+            Some(Lookup::Match("\"Chief\" Officer".to_string()))
+        );
+        assert!(parse_csv("company,\"Example, Inc.\n").is_err());
+        assert!(parse_csv("company,Example, Inc.,Example\n").is_err());
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::domain::{EmailSignature, ErrorCode, ValidationError};
+
+// the form emails are compared in, mailbox names are case-insensitive in practice
+pub fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+// errors for each signature that shares an id or email with others in the same batch,
+// each one pointing at the batch positions it collides with
+pub fn find_duplicates(sigs: &[(usize, &EmailSignature)]) -> Vec<Vec<ValidationError>> {
+    let mut by_id: HashMap<Uuid, Vec<usize>> = HashMap::new();
+    let mut by_email: HashMap<String, Vec<usize>> = HashMap::new();
+    for &(index, sig) in sigs {
+        by_id.entry(sig.id).or_default().push(index);
+        by_email
+            .entry(normalize_email(&sig.email))
+            .or_default()
+            .push(index);
+    }
+
+    let mut errors = vec![Vec::new(); sigs.len()];
+    for (position, &(index, sig)) in sigs.iter().enumerate() {
+        if let Some(error) = duplicate_in_batch("id", index, &by_id[&sig.id]) {
+            errors[position].push(error);
+        }
+        if let Some(error) =
+            duplicate_in_batch("email", index, &by_email[&normalize_email(&sig.email)])
+        {
+            errors[position].push(error);
+        }
+    }
+
+    errors
+}
+
+fn duplicate_in_batch(field: &str, index: usize, group: &[usize]) -> Option<ValidationError> {
+    let others: Vec<String> = group
+        .iter()
+        .filter(|&&other| other != index)
+        .map(usize::to_string)
+        .collect();
+
+    if others.is_empty() {
+        return None;
+    }
+
+    let indexes = others.join(", ");
+    Some(
+        ValidationError::new(
+            field,
+            format!("Duplicate {} in batch (entries {})", field, indexes),
+            ErrorCode::Duplicate,
+        )
+        .with_param("indexes", indexes),
+    )
+}
+
+// an email already used by a different stored signature
+pub fn duplicate_of_stored(stored: &EmailSignature) -> ValidationError {
+    ValidationError::new(
+        "email",
+        format!("Email is already used by signature {}", stored.id),
+        ErrorCode::Duplicate,
+    )
+    .with_param("signature_id", stored.id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicates_in_batch() {
+        let first = EmailSignature::builder().email("jane@example.com").build();
+        let mut same_id = EmailSignature::builder().email("john@example.com").build();
+        same_id.id = first.id;
+        let same_email = EmailSignature::builder().email(" Jane@Example.COM").build();
+        let unique = EmailSignature::builder().email("bob@example.com").build();
+
+        let errors = find_duplicates(&[(0, &first), (1, &same_id), (2, &same_email), (3, &unique)]);
+
+        let fields =
+            |i: usize| -> Vec<&str> { errors[i].iter().map(|e| e.field.as_str()).collect() };
+        assert_eq!(fields(0), vec!["id", "email"]);
+        assert_eq!(fields(1), vec!["id"]);
+        assert_eq!(fields(2), vec!["email"]);
+        assert!(errors[3].is_empty());
+        assert_eq!(errors[0][0].params["indexes"], "1");
+        assert_eq!(errors[0][1].params["indexes"], "2");
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::HashSet;
+
+use async_trait::async_trait;
+use futures_util::future::join_all;
+use url::{Host, Url};
+
+use crate::domain::{EmailSignature, ErrorCode, ValidationError};
+
+// link shortening services hide the real destination, which spam filters penalise
+const URL_SHORTENERS: &[&str] = &[
+    "bit.ly",
+    "buff.ly",
+    "cutt.ly",
+    "goo.gl",
+    "is.gd",
+    "ow.ly",
+    "rb.gy",
+    "rebrand.ly",
+    "shorturl.at",
+    "t.co",
+    "t.ly",
+    "tiny.cc",
+    "tinyurl.com",
+];
+
+// every link a signature can carry, paired with the field it's reported against
+pub fn signature_links(sig: &EmailSignature) -> Vec<(&'static str, &str)> {
+    let links = [
+        ("website", sig.website.as_deref()),
+        ("photo_url", sig.photo_url.as_deref()),
+        ("logo_url", sig.logo_url.as_deref()),
+        ("social.linkedin", sig.social.linkedin.as_deref()),
+        ("social.x", sig.social.x.as_deref()),
+        ("social.github", sig.social.github.as_deref()),
+        (
+            "banner.image_url",
+            sig.banner.as_ref().map(|b| b.image_url.as_str()),
+        ),
+        (
+            "banner.link_url",
+            sig.banner.as_ref().and_then(|b| b.link_url.as_deref()),
+        ),
+    ];
+
+    links
+        .into_iter()
+        .filter_map(|(field, url)| url.map(|url| (field, url)))
+        .collect()
+}
+
+// parse a link and apply the static rules: https only, named hosts, no shorteners
+pub fn check_url(field: &str, url: &str) -> Result<Url, ValidationError> {
+    let parsed = match Url::parse(url.trim()) {
+        Ok(parsed) if parsed.has_host() => parsed,
+        _ => {
+            return Err(ValidationError::new(
+                field,
+                "Invalid URL",
+                ErrorCode::InvalidFormat,
+            ));
+        }
+    };
+
+    if parsed.scheme() != "https" {
+        return Err(ValidationError::new(
+            field,
+            "URL must use https",
+            ErrorCode::InsecureUrl,
+        ));
+    }
+
+    let host = match parsed.host() {
+        Some(Host::Domain(host)) => host.trim_end_matches('.').to_ascii_lowercase(),
+        _ => {
+            return Err(ValidationError::new(
+                field,
+                "URL must use a domain name, not an IP address",
+                ErrorCode::IpAddressHost,
+            ));
+        }
+    };
+
+    if URL_SHORTENERS.contains(&host.as_str()) {
+        return Err(ValidationError::new(
+            field,
+            "URL shorteners are not allowed",
+            ErrorCode::UrlShortener,
+        ));
+    }
+
+    Ok(parsed)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProbeOutcome {
+    Reachable,
+    Unreachable(String),
+}
+
+// checks whether a link resolves, implemented over HTTP in production
+#[async_trait]
+pub trait LinkProber: Send + Sync {
+    async fn probe(&self, url: &Url) -> ProbeOutcome;
+}
+
+// prober answering from a fixed list, for tests and local runs without network access
+#[derive(Debug, Default)]
+pub struct StubProber {
+    unreachable: HashSet<String>,
+}
+
+impl StubProber {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn unreachable(mut self, url: impl Into<String>) -> Self {
+        self.unreachable.insert(url.into());
+        self
+    }
+}
+
+#[async_trait]
+impl LinkProber for StubProber {
+    async fn probe(&self, url: &Url) -> ProbeOutcome {
+        if self.unreachable.contains(url.as_str()) {
+            ProbeOutcome::Unreachable("stubbed as unreachable".to_string())
+        } else {
+            ProbeOutcome::Reachable
+        }
+    }
+}
+
+// reachability stage, run after the static checks have passed
+pub struct LinkValidator {
+    prober: Box<dyn LinkProber>,
+}
+
+impl LinkValidator {
+    pub fn new(prober: impl LinkProber + 'static) -> Self {
+        Self {
+            prober: Box::new(prober),
+        }
+    }
+
+    pub async fn check_reachability(&self, sig: &EmailSignature) -> Vec<ValidationError> {
+        // links failing the static rules are already reported by the validator
+        let links: Vec<(&str, Url)> = signature_links(sig)
+            .into_iter()
+            .filter_map(|(field, url)| check_url(field, url).ok().map(|url| (field, url)))
+            .collect();
+
+        let outcomes = join_all(links.iter().map(|(_, url)| self.prober.probe(url))).await;
+
+        links
+            .into_iter()
+            .zip(outcomes)
+            .filter_map(|((field, _), outcome)| match outcome {
+                ProbeOutcome::Reachable => None,
+                ProbeOutcome::Unreachable(reason) => Some(
+                    ValidationError::new(
+                        field,
+                        format!("URL is not reachable: {}", reason),
+                        ErrorCode::Unreachable,
+                    )
+                    .with_param("reason", reason),
+                ),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(field: &str, url: &str) -> Option<ErrorCode> {
+        check_url(field, url).err().map(|e| e.code)
+    }
+
+    #[test]
+    fn test_check_url() {
+        assert!(check_url("website", "https://example.com/about").is_ok());
+        assert!(matches!(
+            code("website", "example.com"),
+            Some(ErrorCode::InvalidFormat)
+        ));
+        assert!(matches!(
+            code("website", "http://example.com"),
+            Some(ErrorCode::InsecureUrl)
+        ));
+        assert!(matches!(
+            code("website", "https://192.168.0.1/"),
+            Some(ErrorCode::IpAddressHost)
+        ));
+        assert!(matches!(
+            code("website", "https://[::1]/"),
+            Some(ErrorCode::IpAddressHost)
+        ));
+        assert!(matches!(
+            code("website", "https://bit.ly/abc"),
+            Some(ErrorCode::UrlShortener)
+        ));
+    }
+
+    #[actix_web::test]
+    async fn test_check_reachability() {
+        let validator =
+            LinkValidator::new(StubProber::new().unreachable("https://example.com/gone"));
+        let sig = EmailSignature::builder()
+            .website("https://example.com/gone")
+            .photo_url("https://cdn.example.com/me.png")
+            .build();
+
+        let errors = validator.check_reachability(&sig).await;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "website");
+        assert!(matches!(errors[0].code, ErrorCode::Unreachable));
+    }
+}
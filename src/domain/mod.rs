@@ -1,5 +1,19 @@
+pub mod assets;
+pub mod directory;
+pub mod duplicates;
+pub mod links;
 pub mod models;
+pub mod names;
+pub mod phone;
+pub mod policy;
+pub mod rules;
 pub mod validator;
 
+pub use assets::{AssetChecker, AssetKind, AssetRules, AssetStore};
+pub use directory::{DirectoryHandle, OrgDirectory};
+pub use links::{LinkProber, LinkValidator, ProbeOutcome, StubProber};
 pub use models::*;
+pub use phone::normalize_phone;
+pub use policy::PolicyImpact;
+pub use rules::{CrossFieldRule, RulesConfig};
 pub use validator::*;
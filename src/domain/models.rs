@@ -1,20 +1,71 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmailSignature {
     pub id: Uuid,
     pub name: String,
     pub email: String,
+    // office phone
     pub phone: Option<String>,
+    #[serde(default)]
+    pub mobile_phone: Option<String>,
     pub company: Option<String>,
     pub title: Option<String>,
+    #[serde(default)]
+    pub department: Option<String>,
+    #[serde(default)]
+    pub pronouns: Option<String>,
+    #[serde(default)]
+    pub website: Option<String>,
+    // headshot
+    #[serde(default)]
+    pub photo_url: Option<String>,
+    #[serde(default)]
+    pub logo_url: Option<String>,
+    #[serde(default)]
+    pub social: SocialLinks,
+    #[serde(default)]
+    pub address: Option<PostalAddress>,
+    #[serde(default)]
+    pub banner: Option<Banner>,
     pub template_id: Uuid,
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SocialLinks {
+    pub linkedin: Option<String>,
+    pub x: Option<String>,
+    pub github: Option<String>,
+}
+
+impl SocialLinks {
+    pub fn is_empty(&self) -> bool {
+        self.linkedin.is_none() && self.x.is_none() && self.github.is_none()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PostalAddress {
+    pub street: Option<String>,
+    pub city: Option<String>,
+    pub region: Option<String>,
+    pub postal_code: Option<String>,
+    pub country: Option<String>,
+}
+
+// marketing banner shown under the signature
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Banner {
+    pub image_url: String,
+    pub link_url: Option<String>,
+    pub alt_text: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ValidationResult {
     pub signature_id: Uuid,
     pub valid: bool,
@@ -24,18 +75,98 @@ pub struct ValidationResult {
 }
 
 #[derive(Debug, Clone, Serialize)]
+pub struct BatchSummary {
+    pub total: usize,
+    pub valid: usize,
+    pub invalid: usize,
+    // not finished within the deadline, not counted as invalid
+    pub timed_out: usize,
+    pub processing_time_ms: u128,
+}
+
+impl BatchSummary {
+    pub fn new(total: usize, results: &[ValidationResult], processing_time_ms: u128) -> Self {
+        let valid = results.iter().filter(|r| r.valid).count();
+        let timed_out = results
+            .iter()
+            .filter(|r| r.errors.iter().any(|e| e.code == ErrorCode::Timeout))
+            .count();
+
+        Self {
+            total,
+            valid,
+            // a cancelled batch has fewer results than signatures
+            invalid: results.len() - valid - timed_out,
+            timed_out,
+            processing_time_ms,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ValidationError {
     pub field: String,
     pub message: String,
     pub code: ErrorCode,
+    // values interpolated into the message, used to localize it
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, String>,
+    // other fields involved when a rule spans several
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub related: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize)]
+impl ValidationError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>, code: ErrorCode) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+            code,
+            params: BTreeMap::new(),
+            related: Vec::new(),
+        }
+    }
+
+    pub fn with_related(mut self, field: impl Into<String>) -> Self {
+        self.related.push(field.into());
+        self
+    }
+
+    pub fn with_param(mut self, name: impl Into<String>, value: impl ToString) -> Self {
+        self.params.insert(name.into(), value.to_string());
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum ErrorCode {
     Required,
     InvalidFormat,
     TooLong,
     TooShort,
+    DisallowedHost,
+    InsecureUrl,
+    UrlShortener,
+    IpAddressHost,
+    Unreachable,
+    UnsupportedFormat,
+    TooLarge,
+    InvalidDimensions,
+    InvalidAspectRatio,
+    UnknownAsset,
+    MissingAltText,
+    LowContrast,
+    TextAsImage,
+    AmbiguousLinkText,
+    ControlCharacter,
+    InvisibleCharacter,
+    MixedScript,
+    NotInDirectory,
+    DomainMismatch,
+    NameMismatch,
+    FieldConflict,
+    Duplicate,
+    Timeout,
 }
 
 impl EmailSignature {
@@ -43,6 +174,11 @@ impl EmailSignature {
     pub fn builder() -> EmailSignatureBuilder {
         EmailSignatureBuilder::default()
     }
+
+    // canonical form, applied before validating and storing
+    pub fn normalize(&mut self) {
+        self.name = crate::domain::names::normalize_name(&self.name);
+    }
 }
 
 // builder pattern. will be used for test data creation
@@ -51,8 +187,17 @@ pub struct EmailSignatureBuilder {
     name: Option<String>,
     email: Option<String>,
     phone: Option<String>,
+    mobile_phone: Option<String>,
     company: Option<String>,
     title: Option<String>,
+    department: Option<String>,
+    pronouns: Option<String>,
+    website: Option<String>,
+    photo_url: Option<String>,
+    logo_url: Option<String>,
+    social: SocialLinks,
+    address: Option<PostalAddress>,
+    banner: Option<Banner>,
     template_id: Option<Uuid>,
 }
 
@@ -72,6 +217,11 @@ impl EmailSignatureBuilder {
         self
     }
 
+    pub fn mobile_phone(mut self, phone: impl Into<String>) -> Self {
+        self.mobile_phone = Some(phone.into());
+        self
+    }
+
     pub fn company(mut self, company: impl Into<String>) -> Self {
         self.company = Some(company.into());
         self
@@ -82,6 +232,46 @@ impl EmailSignatureBuilder {
         self
     }
 
+    pub fn department(mut self, department: impl Into<String>) -> Self {
+        self.department = Some(department.into());
+        self
+    }
+
+    pub fn pronouns(mut self, pronouns: impl Into<String>) -> Self {
+        self.pronouns = Some(pronouns.into());
+        self
+    }
+
+    pub fn website(mut self, url: impl Into<String>) -> Self {
+        self.website = Some(url.into());
+        self
+    }
+
+    pub fn photo_url(mut self, url: impl Into<String>) -> Self {
+        self.photo_url = Some(url.into());
+        self
+    }
+
+    pub fn logo_url(mut self, url: impl Into<String>) -> Self {
+        self.logo_url = Some(url.into());
+        self
+    }
+
+    pub fn social(mut self, social: SocialLinks) -> Self {
+        self.social = social;
+        self
+    }
+
+    pub fn address(mut self, address: PostalAddress) -> Self {
+        self.address = Some(address);
+        self
+    }
+
+    pub fn banner(mut self, banner: Banner) -> Self {
+        self.banner = Some(banner);
+        self
+    }
+
     pub fn template_id(mut self, id: Uuid) -> Self {
         self.template_id = Some(id);
         self
@@ -93,8 +283,17 @@ impl EmailSignatureBuilder {
             name: self.name.unwrap_or_else(|| "John Doe".to_string()),
             email: self.email.unwrap_or_else(|| "john@example.com".to_string()),
             phone: self.phone,
+            mobile_phone: self.mobile_phone,
             company: self.company,
             title: self.title,
+            department: self.department,
+            pronouns: self.pronouns,
+            website: self.website,
+            photo_url: self.photo_url,
+            logo_url: self.logo_url,
+            social: self.social,
+            address: self.address,
+            banner: self.banner,
             template_id: self.template_id.unwrap_or_else(Uuid::new_v4),
             created_at: Utc::now(),
         }
@@ -0,0 +1,206 @@
+use unicode_normalization::UnicodeNormalization;
+use unicode_security::MixedScript;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::domain::{ErrorCode, ValidationError};
+
+// counted in grapheme clusters, what a reader sees as one character
+pub const MAX_NAME_LEN: usize = 100;
+
+// zero-width and bidi formatting characters, invisible but change how a name compares or displays
+#[rustfmt::skip]
+const INVISIBLE_CHARS: &[char] = &[
+    '\u{00AD}', // soft hyphen
+    '\u{034F}', // combining grapheme joiner
+    '\u{061C}', // arabic letter mark
+    '\u{115F}', // hangul choseong filler
+    '\u{1160}', // hangul jungseong filler
+    '\u{180E}', // mongolian vowel separator
+    '\u{200B}', // zero width space
+    '\u{200E}', // left-to-right mark
+    '\u{200F}', // right-to-left mark
+    // bidi embeddings and overrides
+    '\u{202A}', '\u{202B}', '\u{202C}', '\u{202D}', '\u{202E}',
+    // word joiner and invisible operators
+    '\u{2060}', '\u{2061}', '\u{2062}', '\u{2063}', '\u{2064}',
+    // bidi isolates
+    '\u{2066}', '\u{2067}', '\u{2068}', '\u{2069}',
+    '\u{3164}', // hangul filler
+    '\u{FEFF}', // zero width no-break space
+    '\u{FFA0}', // halfwidth hangul filler
+];
+
+// zero width non-joiner and joiner, part of the spelling in persian and indic names
+const JOINERS: &[char] = &['\u{200C}', '\u{200D}'];
+
+// blocks of the scripts whose spelling relies on the joiners
+#[rustfmt::skip]
+const JOINING_SCRIPTS: &[(char, char)] = &[
+    ('\u{0600}', '\u{08FF}'), // arabic, syriac, thaana, nko and arabic supplements
+    ('\u{0900}', '\u{0DFF}'), // devanagari through sinhala
+    ('\u{1000}', '\u{109F}'), // myanmar
+    ('\u{1780}', '\u{18AF}'), // khmer, mongolian
+    ('\u{FB50}', '\u{FDFF}'), // arabic presentation forms a
+    ('\u{FE70}', '\u{FEFF}'), // arabic presentation forms b
+];
+
+// the form names are stored and compared in
+pub fn normalize_name(name: &str) -> String {
+    name.trim().nfc().collect()
+}
+
+pub fn validate_name(errors: &mut Vec<ValidationError>, name: &str) {
+    if name.trim().is_empty() {
+        errors.push(ValidationError::new(
+            "name",
+            "Name is required",
+            ErrorCode::Required,
+        ));
+        return;
+    }
+
+    let length = name.graphemes(true).count();
+    if length > MAX_NAME_LEN {
+        errors.push(
+            ValidationError::new(
+                "name",
+                format!("Name too long (max {} characters)", MAX_NAME_LEN),
+                ErrorCode::TooLong,
+            )
+            .with_param("max", MAX_NAME_LEN),
+        );
+    }
+
+    if name.chars().any(char::is_control) {
+        errors.push(ValidationError::new(
+            "name",
+            "Name contains control characters",
+            ErrorCode::ControlCharacter,
+        ));
+    }
+
+    if name.chars().any(|c| INVISIBLE_CHARS.contains(&c)) || has_stray_joiner(name) {
+        errors.push(ValidationError::new(
+            "name",
+            "Name contains invisible characters",
+            ErrorCode::InvisibleCharacter,
+        ));
+    }
+
+    // names may combine scripts across words ("山田 Taro"), but a word mixing
+    // scripts is the classic homoglyph trick, e.g. a cyrillic "о" in "John"
+    if let Some(word) = name
+        .split_whitespace()
+        .find(|word| !word.is_single_script())
+    {
+        errors.push(
+            ValidationError::new(
+                "name",
+                format!("Name mixes writing systems in \"{}\"", word),
+                ErrorCode::MixedScript,
+            )
+            .with_param("word", word),
+        );
+    }
+}
+
+// a joiner is only needed between two characters of a script that uses it,
+// anywhere else (leading, trailing, doubled, in latin text) it just hides something
+fn has_stray_joiner(name: &str) -> bool {
+    let chars: Vec<char> = name.chars().collect();
+    chars.iter().enumerate().any(|(i, c)| {
+        JOINERS.contains(c)
+            && !(i > 0
+                && in_joining_script(chars[i - 1])
+                && chars
+                    .get(i + 1)
+                    .is_some_and(|&next| in_joining_script(next)))
+    })
+}
+
+fn in_joining_script(c: char) -> bool {
+    JOINING_SCRIPTS
+        .iter()
+        .any(|&(first, last)| (first..=last).contains(&c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(name: &str) -> Vec<ErrorCode> {
+        let mut errors = Vec::new();
+        validate_name(&mut errors, name);
+        errors.into_iter().map(|e| e.code).collect()
+    }
+
+    #[test]
+    fn test_length_in_graphemes() {
+        // 40 characters but 120 bytes
+        let japanese = "山".repeat(40);
+        assert!(codes(&japanese).is_empty());
+
+        // combining marks and emoji sequences count once
+        let accented = "e\u{0301}".repeat(100);
+        assert!(codes(&accented).is_empty());
+        assert!(matches!(
+            codes(&"a".repeat(101)).as_slice(),
+            [ErrorCode::TooLong]
+        ));
+    }
+
+    #[test]
+    fn test_hidden_characters() {
+        assert!(matches!(
+            codes("John\u{200D}\u{200D}Doe").as_slice(),
+            [ErrorCode::InvisibleCharacter]
+        ));
+        assert!(matches!(
+            codes("John\u{0007}Doe").as_slice(),
+            [ErrorCode::ControlCharacter]
+        ));
+        assert!(matches!(
+            codes("Jane\u{202E}eoD").as_slice(),
+            [ErrorCode::InvisibleCharacter]
+        ));
+    }
+
+    #[test]
+    fn test_joiners() {
+        // persian "alireza" and hindi "ksha" are spelled with them
+        assert!(codes("علی\u{200C}رضا").is_empty());
+        assert!(codes("क्\u{200D}ष").is_empty());
+
+        for name in [
+            "\u{200C}علی",
+            "علی\u{200C}",
+            "علی\u{200C}\u{200C}رضا",
+            "Jo\u{200D}hn",
+            "علی \u{200C}رضا",
+        ] {
+            assert!(
+                matches!(codes(name).as_slice(), [ErrorCode::InvisibleCharacter]),
+                "{:?}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_mixed_script() {
+        // cyrillic "о"
+        assert!(matches!(
+            codes("J\u{043E}hn Doe").as_slice(),
+            [ErrorCode::MixedScript]
+        ));
+        assert!(codes("山田 Taro").is_empty());
+        assert!(codes("やまだ たろう").is_empty());
+        assert!(codes("山田たろう").is_empty());
+        assert!(codes("Иван Петров").is_empty());
+    }
+
+    #[test]
+    fn test_normalize_name() {
+        assert_eq!(normalize_name(" Zoe\u{0308} "), "Zo\u{00EB}");
+    }
+}
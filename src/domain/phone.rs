@@ -0,0 +1,48 @@
+// normalize a phone number to the compact `+<digits>` form used in exports
+// separators like spaces, dots, dashes and parentheses are dropped
+// returns None if anything other than digits and separators is present
+pub fn normalize_phone(phone: &str) -> Option<String> {
+    let trimmed = phone.trim();
+    let (international, rest) = match trimmed.strip_prefix('+') {
+        Some(rest) => (true, rest),
+        None => (false, trimmed),
+    };
+
+    let mut digits = String::with_capacity(rest.len());
+    for c in rest.chars() {
+        match c {
+            '0'..='9' => digits.push(c),
+            ' ' | '-' | '.' | '(' | ')' => {}
+            _ => return None,
+        }
+    }
+
+    if digits.is_empty() {
+        return None;
+    }
+
+    Some(if international {
+        format!("+{}", digits)
+    } else {
+        digits
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_phone() {
+        assert_eq!(
+            normalize_phone("+1 (555) 010-0199").as_deref(),
+            Some("+15550100199")
+        );
+        assert_eq!(
+            normalize_phone("020 7946 0958").as_deref(),
+            Some("02079460958")
+        );
+        assert_eq!(normalize_phone("call me"), None);
+        assert_eq!(normalize_phone("+"), None);
+    }
+}
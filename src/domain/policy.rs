@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::domain::{ErrorCode, ValidationError, ValidationResult};
+
+// signature ids listed per group, enough to look into a few examples
+const EXAMPLES: usize = 10;
+
+// how a candidate rule set would change the outcome for a set of signatures
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PolicyImpact {
+    pub checked: usize,
+    pub became_invalid: usize,
+    pub became_valid: usize,
+    pub unchanged_valid: usize,
+    pub unchanged_invalid: usize,
+    // errors of the signatures that would start failing, under the candidate rules
+    pub newly_failing: Vec<ImpactGroup>,
+    // errors of the signatures that would start passing, under the current rules
+    pub newly_passing: Vec<ImpactGroup>,
+}
+
+// signatures flipping because of one rule, most affected first
+#[derive(Debug, Clone, Serialize)]
+pub struct ImpactGroup {
+    pub rule: String,
+    pub code: ErrorCode,
+    pub signatures: usize,
+    pub examples: Vec<Uuid>,
+}
+
+impl PolicyImpact {
+    // current and candidate results for the same signatures, in the same order
+    pub fn compare(current: &[ValidationResult], candidate: &[ValidationResult]) -> Self {
+        let mut impact = Self {
+            checked: current.len(),
+            ..Self::default()
+        };
+        let mut failing = Groups::default();
+        let mut passing = Groups::default();
+
+        for (before, after) in current.iter().zip(candidate) {
+            match (before.valid, after.valid) {
+                (true, true) => impact.unchanged_valid += 1,
+                (false, false) => impact.unchanged_invalid += 1,
+                (true, false) => {
+                    impact.became_invalid += 1;
+                    failing.add(after);
+                }
+                (false, true) => {
+                    impact.became_valid += 1;
+                    passing.add(before);
+                }
+            }
+        }
+
+        impact.newly_failing = failing.into_sorted();
+        impact.newly_passing = passing.into_sorted();
+        impact
+    }
+}
+
+// the rule behind an error, the field for the checks every signature goes through
+pub fn rule_of(error: &ValidationError) -> String {
+    error
+        .params
+        .get("rule")
+        .cloned()
+        .unwrap_or_else(|| error.field.clone())
+}
+
+#[derive(Default)]
+struct Groups(HashMap<(String, ErrorCode), ImpactGroup>);
+
+impl Groups {
+    fn add(&mut self, result: &ValidationResult) {
+        let mut seen = Vec::new();
+        for error in &result.errors {
+            let rule = rule_of(error);
+            let key = (rule.clone(), error.code.clone());
+            // a signature counts once per group, however many errors it has there
+            if seen.contains(&key) {
+                continue;
+            }
+            seen.push(key.clone());
+
+            let group = self.0.entry(key).or_insert_with(|| ImpactGroup {
+                rule,
+                code: error.code.clone(),
+                signatures: 0,
+                examples: Vec::new(),
+            });
+            group.signatures += 1;
+            if group.examples.len() < EXAMPLES {
+                group.examples.push(result.signature_id);
+            }
+        }
+    }
+
+    fn into_sorted(self) -> Vec<ImpactGroup> {
+        let mut groups: Vec<ImpactGroup> = self.0.into_values().collect();
+        groups.sort_by(|a, b| {
+            b.signatures
+                .cmp(&a.signatures)
+                .then_with(|| a.rule.cmp(&b.rule))
+        });
+        groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{EmailSignature, RulesConfig, SignatureValidator};
+
+    fn validate(rules: &RulesConfig, sigs: &[EmailSignature]) -> Vec<ValidationResult> {
+        let validator = rules
+            .build()
+            .unwrap()
+            .into_iter()
+            .fold(SignatureValidator::new(), SignatureValidator::with_rule);
+        sigs.iter().map(|sig| validator.validate(sig)).collect()
+    }
+
+    #[test]
+    fn test_compare() {
+        let sigs = vec![
+            EmailSignature::builder()
+                .name("Jane Doe")
+                .email("jane@example.com")
+                .build(),
+            EmailSignature::builder()
+                .name("Jane Doe")
+                .email("zz@example.com")
+                .build(),
+            EmailSignature::builder()
+                .name("Max Power")
+                .email("xy@example.com")
+                .build(),
+            EmailSignature::builder().email("invalid").build(),
+        ];
+        let current = RulesConfig::default();
+        let candidate = RulesConfig {
+            name_matches_email: true,
+            ..RulesConfig::default()
+        };
+
+        let impact =
+            PolicyImpact::compare(&validate(&current, &sigs), &validate(&candidate, &sigs));
+        assert_eq!(impact.checked, 4);
+        assert_eq!(impact.became_invalid, 2);
+        assert_eq!(impact.unchanged_valid, 1);
+        assert_eq!(impact.unchanged_invalid, 1);
+        assert_eq!(impact.newly_failing.len(), 1);
+        assert_eq!(impact.newly_failing[0].rule, "name_matches_email");
+        assert_eq!(impact.newly_failing[0].code, ErrorCode::NameMismatch);
+        assert_eq!(impact.newly_failing[0].signatures, 2);
+        assert_eq!(
+            impact.newly_failing[0].examples,
+            vec![sigs[1].id, sigs[2].id]
+        );
+
+        // and back again
+        let impact =
+            PolicyImpact::compare(&validate(&candidate, &sigs), &validate(&current, &sigs));
+        assert_eq!(impact.became_valid, 2);
+        assert_eq!(impact.newly_passing[0].rule, "name_matches_email");
+        assert!(impact.newly_failing.is_empty());
+    }
+}
@@ -0,0 +1,359 @@
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use unicode_normalization::{UnicodeNormalization, char::is_combining_mark};
+
+use crate::domain::{EmailSignature, ErrorCode, ValidationError};
+
+// fields a configured rule can refer to
+pub const RULE_FIELDS: &[&str] = &[
+    "name",
+    "email",
+    "phone",
+    "mobile_phone",
+    "company",
+    "title",
+    "department",
+    "pronouns",
+    "website",
+];
+
+// names the built-in rules report in the `rule` param of their errors,
+// configured field rules report their own
+pub const EMAIL_DOMAINS_RULE: &str = "email_domains";
+pub const NAME_EMAIL_RULE: &str = "name_matches_email";
+
+// a rule that looks at several fields of a signature together,
+// run after every field has been checked on its own
+pub trait CrossFieldRule: Send + Sync {
+    fn check(&self, sig: &EmailSignature, errors: &mut Vec<ValidationError>);
+}
+
+pub fn field_value<'a>(sig: &'a EmailSignature, field: &str) -> Option<&'a str> {
+    match field {
+        "name" => Some(&sig.name),
+        "email" => Some(&sig.email),
+        "phone" => sig.phone.as_deref(),
+        "mobile_phone" => sig.mobile_phone.as_deref(),
+        "company" => sig.company.as_deref(),
+        "title" => sig.title.as_deref(),
+        "department" => sig.department.as_deref(),
+        "pronouns" => sig.pronouns.as_deref(),
+        "website" => sig.website.as_deref(),
+        _ => None,
+    }
+}
+
+// the email has to be on one of the company's registered domains, subdomains included
+pub struct EmailDomainRule {
+    // keyed by lowercased company name
+    domains: HashMap<String, Vec<String>>,
+}
+
+impl EmailDomainRule {
+    pub fn new(domains: &HashMap<String, Vec<String>>) -> Self {
+        let domains = domains
+            .iter()
+            .map(|(company, domains)| {
+                let domains = domains.iter().map(|d| d.to_ascii_lowercase()).collect();
+                (company.to_lowercase(), domains)
+            })
+            .collect();
+
+        Self { domains }
+    }
+}
+
+impl CrossFieldRule for EmailDomainRule {
+    fn check(&self, sig: &EmailSignature, errors: &mut Vec<ValidationError>) {
+        let Some(company) = &sig.company else {
+            return;
+        };
+        let Some(domains) = self.domains.get(&company.to_lowercase()) else {
+            return;
+        };
+        let Some((_, domain)) = sig.email.rsplit_once('@') else {
+            return;
+        };
+
+        let domain = domain.to_ascii_lowercase();
+        let registered = domains
+            .iter()
+            .any(|d| domain == *d || domain.ends_with(&format!(".{}", d)));
+
+        if !registered {
+            let domains = domains.join(", ");
+            errors.push(
+                ValidationError::new(
+                    "email",
+                    format!(
+                        "Email domain is not registered for {} ({})",
+                        company, domains
+                    ),
+                    ErrorCode::DomainMismatch,
+                )
+                .with_related("company")
+                .with_param("company", company)
+                .with_param("domains", domains)
+                .with_param("rule", EMAIL_DOMAINS_RULE),
+            );
+        }
+    }
+}
+
+// the email local part should be recognisably the person's, "jdoe" or "john.doe" for John Doe
+pub struct NameEmailRule;
+
+impl CrossFieldRule for NameEmailRule {
+    fn check(&self, sig: &EmailSignature, errors: &mut Vec<ValidationError>) {
+        let Some((local, _)) = sig.email.split_once('@') else {
+            return;
+        };
+
+        let local: String = local
+            .split('+')
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .collect::<String>()
+            .to_ascii_lowercase();
+
+        // names without a latin spelling can't be compared to the address
+        let tokens = name_tokens(&sig.name);
+        if tokens.is_empty() || local.is_empty() {
+            return;
+        }
+
+        let initials: String = tokens.iter().filter_map(|t| t.chars().next()).collect();
+        let plausible = local == initials
+            || tokens
+                .iter()
+                .any(|t| t.len() >= 2 && local.contains(t.as_str()));
+
+        if !plausible {
+            errors.push(
+                ValidationError::new(
+                    "name",
+                    "Name does not match the email address",
+                    ErrorCode::NameMismatch,
+                )
+                .with_related("email")
+                .with_param("rule", NAME_EMAIL_RULE),
+            );
+        }
+    }
+}
+
+// lowercase latin words of a name with accents folded, "Zoë O'Brien" -> ["zoe", "obrien"]
+fn name_tokens(name: &str) -> Vec<String> {
+    name.split_whitespace()
+        .map(|word| {
+            word.nfd()
+                .filter(|c| !is_combining_mark(*c))
+                .filter(char::is_ascii_alphabetic)
+                .collect::<String>()
+                .to_ascii_lowercase()
+        })
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+// when one field matches a pattern, another must (not) match one,
+// e.g. an intern's title with a director's phone extension
+pub struct FieldPatternRule {
+    name: String,
+    when_field: String,
+    when: Regex,
+    then_field: String,
+    must_match: Option<Regex>,
+    must_not_match: Option<Regex>,
+}
+
+impl FieldPatternRule {
+    pub fn new(config: &FieldRuleConfig) -> anyhow::Result<Self> {
+        for field in [&config.when_field, &config.then_field] {
+            if !RULE_FIELDS.contains(&field.as_str()) {
+                anyhow::bail!("Rule {} refers to unknown field {}", config.name, field);
+            }
+        }
+
+        let compile = |pattern: &str| {
+            Regex::new(pattern)
+                .map_err(|e| anyhow::anyhow!("Rule {} has an invalid pattern: {}", config.name, e))
+        };
+
+        Ok(Self {
+            name: config.name.clone(),
+            when_field: config.when_field.clone(),
+            when: compile(&config.when_matches)?,
+            then_field: config.then_field.clone(),
+            must_match: config.must_match.as_deref().map(compile).transpose()?,
+            must_not_match: config.must_not_match.as_deref().map(compile).transpose()?,
+        })
+    }
+}
+
+impl CrossFieldRule for FieldPatternRule {
+    fn check(&self, sig: &EmailSignature, errors: &mut Vec<ValidationError>) {
+        if !field_value(sig, &self.when_field).is_some_and(|v| self.when.is_match(v)) {
+            return;
+        }
+
+        let value = field_value(sig, &self.then_field);
+        let missing = self
+            .must_match
+            .as_ref()
+            .is_some_and(|re| !value.is_some_and(|v| re.is_match(v)));
+        let forbidden = self
+            .must_not_match
+            .as_ref()
+            .is_some_and(|re| value.is_some_and(|v| re.is_match(v)));
+
+        if missing || forbidden {
+            errors.push(
+                ValidationError::new(
+                    &self.then_field,
+                    format!(
+                        "{} is not allowed with this {}",
+                        self.then_field, self.when_field
+                    ),
+                    ErrorCode::FieldConflict,
+                )
+                .with_related(&self.when_field)
+                .with_param("rule", &self.name),
+            );
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FieldRuleConfig {
+    pub name: String,
+    pub when_field: String,
+    pub when_matches: String,
+    pub then_field: String,
+    pub must_match: Option<String>,
+    pub must_not_match: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RulesConfig {
+    // registered email domains keyed by company name
+    pub email_domains: HashMap<String, Vec<String>>,
+    pub name_matches_email: bool,
+    pub field_rules: Vec<FieldRuleConfig>,
+}
+
+impl RulesConfig {
+    pub fn build(&self) -> anyhow::Result<Vec<Arc<dyn CrossFieldRule>>> {
+        let mut rules: Vec<Arc<dyn CrossFieldRule>> = Vec::new();
+
+        if !self.email_domains.is_empty() {
+            rules.push(Arc::new(EmailDomainRule::new(&self.email_domains)));
+        }
+        if self.name_matches_email {
+            rules.push(Arc::new(NameEmailRule));
+        }
+        for config in &self.field_rules {
+            rules.push(Arc::new(FieldPatternRule::new(config)?));
+        }
+
+        Ok(rules)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(rule: &dyn CrossFieldRule, sig: &EmailSignature) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        rule.check(sig, &mut errors);
+        errors
+    }
+
+    #[test]
+    fn test_email_domain() {
+        let rule = EmailDomainRule::new(&HashMap::from([(
+            "Example Corp".to_string(),
+            vec!["example.com".to_string()],
+        )]));
+
+        let sig = EmailSignature::builder()
+            .email("jane@eu.example.com")
+            .company("Example Corp")
+            .build();
+        assert!(run(&rule, &sig).is_empty());
+
+        let sig = EmailSignature::builder()
+            .email("jane@gmail.com")
+            .company("example corp")
+            .build();
+        let errors = run(&rule, &sig);
+        assert_eq!(errors[0].field, "email");
+        assert_eq!(errors[0].related, vec!["company"]);
+        assert!(matches!(errors[0].code, ErrorCode::DomainMismatch));
+    }
+
+    #[test]
+    fn test_name_matches_email() {
+        let sig =
+            |name: &str, email: &str| EmailSignature::builder().name(name).email(email).build();
+
+        assert!(run(&NameEmailRule, &sig("John Doe", "jdoe@example.com")).is_empty());
+        assert!(
+            run(
+                &NameEmailRule,
+                &sig("John Doe", "john.doe+news@example.com")
+            )
+            .is_empty()
+        );
+        assert!(run(&NameEmailRule, &sig("Zoë Smith", "zoe@example.com")).is_empty());
+        assert!(run(&NameEmailRule, &sig("John Doe", "jd@example.com")).is_empty());
+        assert!(run(&NameEmailRule, &sig("山田 太郎", "taro@example.com")).is_empty());
+        assert!(matches!(
+            run(&NameEmailRule, &sig("John Doe", "alice@example.com"))[0].code,
+            ErrorCode::NameMismatch
+        ));
+    }
+
+    #[test]
+    fn test_field_pattern() {
+        let rule = FieldPatternRule::new(&FieldRuleConfig {
+            name: "intern-extension".to_string(),
+            when_field: "title".to_string(),
+            when_matches: r"(?i)\bintern\b".to_string(),
+            then_field: "phone".to_string(),
+            must_match: None,
+            must_not_match: Some(r"-9\d{2}$".to_string()),
+        })
+        .unwrap();
+
+        let sig =
+            |title: &str, phone: &str| EmailSignature::builder().title(title).phone(phone).build();
+
+        assert!(run(&rule, &sig("Software Intern", "+1 555 0100-123")).is_empty());
+        assert!(run(&rule, &sig("Director", "+1 555 0100-901")).is_empty());
+
+        let errors = run(&rule, &sig("Intern", "+1 555 0100-901"));
+        assert_eq!(errors[0].field, "phone");
+        assert_eq!(errors[0].related, vec!["title"]);
+        assert_eq!(errors[0].params["rule"], "intern-extension");
+    }
+
+    #[test]
+    fn test_unknown_field_rejected() {
+        let config = FieldRuleConfig {
+            name: "bad".to_string(),
+            when_field: "salary".to_string(),
+            when_matches: ".*".to_string(),
+            then_field: "phone".to_string(),
+            must_match: None,
+            must_not_match: None,
+        };
+        assert!(FieldPatternRule::new(&config).is_err());
+    }
+}
@@ -1,57 +1,160 @@
-use crate::domain::{EmailSignature, ErrorCode, ValidationError, ValidationResult};
+use crate::domain::{
+    AssetChecker, CrossFieldRule, DirectoryHandle, EmailSignature, ErrorCode, PostalAddress,
+    ValidationError, ValidationResult, links, names, normalize_phone,
+};
 use chrono::Utc;
 use once_cell::sync::Lazy;
 use rayon::iter::IntoParallelRefIterator;
 use rayon::prelude::*;
 use regex::Regex;
+use std::sync::Arc;
+use url::Url;
 
 // compile regex once
 static EMAIL_REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"^[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+\.[a-zA-Z]{2,}$").unwrap());
 
-pub struct SignatureValidator;
+// hosts accepted for each social profile, subdomains included
+const LINKEDIN_HOSTS: &[&str] = &["linkedin.com"];
+const X_HOSTS: &[&str] = &["x.com", "twitter.com"];
+const GITHUB_HOSTS: &[&str] = &["github.com"];
+
+const MAX_PRONOUNS_LEN: usize = 30;
+const MAX_DEPARTMENT_LEN: usize = 100;
+// E.164 allows at most 15 digits
+const MIN_PHONE_DIGITS: usize = 7;
+const MAX_PHONE_DIGITS: usize = 15;
+
+pub struct SignatureValidator {
+    assets: Option<Arc<AssetChecker>>,
+    directory: Option<Arc<DirectoryHandle>>,
+    rules: Vec<Arc<dyn CrossFieldRule>>,
+}
 
 impl SignatureValidator {
     pub fn new() -> Self {
-        Self
+        Self {
+            assets: None,
+            directory: None,
+            rules: Vec::new(),
+        }
+    }
+
+    // rules spanning several fields, run after the per-field checks
+    pub fn with_rule(mut self, rule: Arc<dyn CrossFieldRule>) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    // check company and title against the organization directory
+    pub fn with_directory(mut self, directory: Arc<DirectoryHandle>) -> Self {
+        self.directory = Some(directory);
+        self
+    }
+
+    // bring a signature into canonical form before it's validated or stored
+    pub fn normalize(&self, sig: &mut EmailSignature) {
+        sig.normalize();
+        if let Some(directory) = &self.directory {
+            directory.canonicalize(sig);
+        }
+    }
+
+    // check uploaded images referenced by signatures against the asset rules
+    pub fn with_assets(mut self, assets: Arc<AssetChecker>) -> Self {
+        self.assets = Some(assets);
+        self
+    }
+
+    // changes whenever the same signature could validate differently
+    pub fn rules_version(&self) -> String {
+        let directory = self.directory.as_ref().map_or(0, |d| d.generation());
+        format!("{}+directory.{}", env!("CARGO_PKG_VERSION"), directory)
     }
 
     pub fn validate(&self, sig: &EmailSignature) -> ValidationResult {
         let mut errors = Vec::new();
+        let mut warnings = Vec::new();
 
         // validate name
-        if sig.name.trim().is_empty() {
-            errors.push(ValidationError {
-                field: "name".to_string(),
-                message: "Name is required".to_string(),
-                code: ErrorCode::Required,
-            })
+        names::validate_name(&mut errors, &sig.name);
+
+        // validate email
+        if !EMAIL_REGEX.is_match(&sig.email) {
+            errors.push(ValidationError::new(
+                "email",
+                "Invalid email format",
+                ErrorCode::InvalidFormat,
+            ));
         }
 
-        if sig.name.len() > 100 {
-            errors.push(ValidationError {
-                field: "name".to_string(),
-                message: "Name too long (max 100 characters)".to_string(),
-                code: ErrorCode::TooLong,
-            });
+        // validate phones
+        validate_phone(&mut errors, "phone", sig.phone.as_deref());
+        validate_phone(&mut errors, "mobile_phone", sig.mobile_phone.as_deref());
+
+        // validate free text fields
+        if let Some(pronouns) = &sig.pronouns
+            && pronouns.chars().count() > MAX_PRONOUNS_LEN
+        {
+            errors.push(
+                ValidationError::new(
+                    "pronouns",
+                    format!("Pronouns too long (max {} characters)", MAX_PRONOUNS_LEN),
+                    ErrorCode::TooLong,
+                )
+                .with_param("max", MAX_PRONOUNS_LEN),
+            );
         }
 
-        // validate email
-        if !EMAIL_REGEX.is_match(&sig.email) {
-            errors.push(ValidationError {
-                field: "email".to_string(),
-                message: "Invalid email format".to_string(),
-                code: ErrorCode::InvalidFormat,
-            });
+        if let Some(department) = &sig.department
+            && department.chars().count() > MAX_DEPARTMENT_LEN
+        {
+            errors.push(
+                ValidationError::new(
+                    "department",
+                    format!(
+                        "Department too long (max {} characters)",
+                        MAX_DEPARTMENT_LEN
+                    ),
+                    ErrorCode::TooLong,
+                )
+                .with_param("max", MAX_DEPARTMENT_LEN),
+            );
+        }
+
+        // validate links
+        for (field, url) in links::signature_links(sig) {
+            match links::check_url(field, url) {
+                Ok(parsed) => validate_social_host(&mut errors, field, &parsed),
+                Err(e) => errors.push(e),
+            }
         }
 
-        // TODO: add phone validation & other field validation
+        // validate address
+        if let Some(address) = &sig.address {
+            validate_address(&mut errors, address);
+        }
+
+        // validate against the organization directory
+        if let Some(directory) = &self.directory {
+            directory.check(sig, &mut errors);
+        }
+
+        // validate fields against each other
+        for rule in &self.rules {
+            rule.check(sig, &mut errors);
+        }
+
+        // validate images
+        if let Some(assets) = &self.assets {
+            assets.check(sig, &mut errors, &mut warnings);
+        }
 
         ValidationResult {
             signature_id: sig.id,
             valid: errors.is_empty(),
             errors,
-            warnings: vec![],
+            warnings,
             validated_at: Utc::now(),
         }
     }
@@ -61,9 +164,73 @@ impl SignatureValidator {
     }
 }
 
+impl Default for SignatureValidator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn validate_phone(errors: &mut Vec<ValidationError>, field: &str, phone: Option<&str>) {
+    let Some(phone) = phone else {
+        return;
+    };
+
+    let digits = normalize_phone(phone).map(|p| p.trim_start_matches('+').len());
+    if !matches!(digits, Some(n) if (MIN_PHONE_DIGITS..=MAX_PHONE_DIGITS).contains(&n)) {
+        errors.push(ValidationError::new(
+            field,
+            "Invalid phone number",
+            ErrorCode::InvalidFormat,
+        ));
+    }
+}
+
+// social profile links must point at the matching network
+fn validate_social_host(errors: &mut Vec<ValidationError>, field: &str, url: &Url) {
+    let allowed_hosts = match field {
+        "social.linkedin" => LINKEDIN_HOSTS,
+        "social.x" => X_HOSTS,
+        "social.github" => GITHUB_HOSTS,
+        _ => return,
+    };
+
+    let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+    let allowed = allowed_hosts
+        .iter()
+        .any(|allowed| host == *allowed || host.ends_with(&format!(".{}", allowed)));
+
+    if !allowed {
+        let hosts = allowed_hosts.join(" or ");
+        errors.push(
+            ValidationError::new(
+                field,
+                format!("Link must point to {}", hosts),
+                ErrorCode::DisallowedHost,
+            )
+            .with_param("hosts", hosts),
+        );
+    }
+}
+
+// an address is only useful on a signature if it can be located
+fn validate_address(errors: &mut Vec<ValidationError>, address: &PostalAddress) {
+    let required = [
+        ("address.street", &address.street, "Street is required"),
+        ("address.city", &address.city, "City is required"),
+        ("address.country", &address.country, "Country is required"),
+    ];
+
+    for (field, value, message) in required {
+        if value.as_deref().is_none_or(|v| v.trim().is_empty()) {
+            errors.push(ValidationError::new(field, message, ErrorCode::Required));
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::{RulesConfig, SocialLinks};
 
     #[test]
     fn test_valid_signature() {
@@ -78,6 +245,116 @@ mod tests {
         assert!(result.errors.is_empty());
     }
 
+    #[test]
+    fn test_extended_fields_valid() {
+        let validator = SignatureValidator::new();
+        let sig = EmailSignature::builder()
+            .phone("+1 555 010 0199")
+            .mobile_phone("+44 7700 900123")
+            .pronouns("she/her")
+            .department("Engineering")
+            .photo_url("https://cdn.example.com/jane.png")
+            .social(SocialLinks {
+                linkedin: Some("https://www.linkedin.com/in/jane".to_string()),
+                x: Some("https://x.com/jane".to_string()),
+                github: Some("https://github.com/jane".to_string()),
+            })
+            .address(PostalAddress {
+                street: Some("1 Main St".to_string()),
+                city: Some("Springfield".to_string()),
+                country: Some("US".to_string()),
+                ..Default::default()
+            })
+            .build();
+
+        let result = validator.validate(&sig);
+        assert!(result.valid, "{:?}", result.errors);
+    }
+
+    #[test]
+    fn test_extended_fields_invalid() {
+        let validator = SignatureValidator::new();
+        let sig = EmailSignature::builder()
+            .mobile_phone("12")
+            .photo_url("not a url")
+            .social(SocialLinks {
+                linkedin: Some("https://linkedin.evil.com/in/jane".to_string()),
+                ..Default::default()
+            })
+            .address(PostalAddress {
+                street: Some("1 Main St".to_string()),
+                ..Default::default()
+            })
+            .build();
+
+        let result = validator.validate(&sig);
+        let fields: Vec<&str> = result.errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "mobile_phone",
+                "photo_url",
+                "social.linkedin",
+                "address.city",
+                "address.country"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_directory_normalizes_and_flags() {
+        let directory = Arc::new(DirectoryHandle::fixed(crate::domain::OrgDirectory::new(
+            &["Example Corp"],
+            &["Senior Software Engineer"],
+        )));
+        let validator = SignatureValidator::new().with_directory(directory);
+
+        let mut sig = EmailSignature::builder()
+            .company("example corp")
+            .title("sr. software eng")
+            .build();
+        validator.normalize(&mut sig);
+        assert_eq!(sig.company.as_deref(), Some("Example Corp"));
+        assert_eq!(sig.title.as_deref(), Some("Senior Software Engineer"));
+        assert!(validator.validate(&sig).valid);
+
+        let sig = EmailSignature::builder()
+            .title("Senior Sofware Engineer")
+            .build();
+        let result = validator.validate(&sig);
+        assert!(matches!(result.errors[0].code, ErrorCode::NotInDirectory));
+        assert_eq!(
+            result.errors[0].params["suggestion"],
+            "Senior Software Engineer"
+        );
+    }
+
+    #[test]
+    fn test_cross_field_rules() {
+        let rules = RulesConfig {
+            email_domains: [("Example Corp".to_string(), vec!["example.com".to_string()])].into(),
+            name_matches_email: true,
+            ..Default::default()
+        };
+        let validator = rules
+            .build()
+            .unwrap()
+            .into_iter()
+            .fold(SignatureValidator::new(), SignatureValidator::with_rule);
+
+        let sig = EmailSignature::builder()
+            .name("Jane Roe")
+            .email("john@other.com")
+            .company("Example Corp")
+            .build();
+        let result = validator.validate(&sig);
+        let codes: Vec<&ErrorCode> = result.errors.iter().map(|e| &e.code).collect();
+        assert_eq!(
+            codes,
+            vec![&ErrorCode::DomainMismatch, &ErrorCode::NameMismatch]
+        );
+    }
+
     #[test]
     fn test_invalid_email() {
         let validator = SignatureValidator::new();
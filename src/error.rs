@@ -1,13 +1,56 @@
-use actix_web::http::StatusCode;
+use actix_web::ResponseError;
+use actix_web::error::{JsonPayloadError, PathError, QueryPayloadError};
+use actix_web::http::{Method, StatusCode};
 use anyhow::Error as AnyhowError;
+use serde::{Deserialize, Serialize};
 use std::fmt::{self, Display};
+use std::time::Duration;
+use tracing::error;
+
+// error responses are RFC 7807 problem details
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+// prefix of each problem's `type`, followed by its code
+pub const PROBLEM_TYPE_BASE: &str = "urn:email-processor:problem:";
 
 #[derive(Debug)]
 pub enum AppError {
     Config(AnyhowError),
     Validation(String),
+    NotFound(String),
+    // a path that exists, requested with a method other than the ones it takes
+    MethodNotAllowed(String, &'static [Method]),
+    Unauthorized(String),
+    PayloadTooLarge(String),
+    UnsupportedMediaType(String),
+    // an idempotency key sent again with a different request
+    IdempotencyKeyReused(String),
     Render(String),
     Internal(String),
+    Overloaded(Overload),
+}
+
+// the pipeline is full, the client should retry later
+#[derive(Debug, Clone, PartialEq)]
+pub struct Overload {
+    pub reason: OverloadReason,
+    pub retry_after: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverloadReason {
+    // too many batches waiting, the client is sending faster than we can work
+    TooManyBatches,
+    // too many signatures in flight overall
+    Saturated,
+}
+
+impl OverloadReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TooManyBatches => "too_many_batches",
+            Self::Saturated => "saturated",
+        }
+    }
 }
 
 impl Display for AppError {
@@ -15,8 +58,18 @@ impl Display for AppError {
         match self {
             Self::Config(e) => write!(f, "Configuration error: {}", e),
             Self::Validation(e) => write!(f, "Validation error: {}", e),
+            Self::NotFound(e) => write!(f, "Not found: {}", e),
+            Self::MethodNotAllowed(e, _) => write!(f, "Method not allowed: {}", e),
+            Self::Unauthorized(e) => write!(f, "Unauthorized: {}", e),
+            Self::PayloadTooLarge(e) => write!(f, "Payload too large: {}", e),
+            Self::UnsupportedMediaType(e) => write!(f, "Unsupported media type: {}", e),
+            Self::IdempotencyKeyReused(e) => write!(f, "Idempotency key reused: {}", e),
             Self::Render(e) => write!(f, "Rendering error: {}", e),
             Self::Internal(e) => write!(f, "Internal eror: {}", e),
+            Self::Overloaded(o) => match o.reason {
+                OverloadReason::TooManyBatches => write!(f, "Too many batches queued"),
+                OverloadReason::Saturated => write!(f, "Server is at capacity"),
+            },
         }
     }
 }
@@ -29,15 +82,158 @@ impl From<AnyhowError> for AppError {
 
 // impl Error for AppError {}
 
-impl actix_web::error::ResponseError for AppError {
-    fn error_response(&self) -> actix_web::HttpResponse {
-        let status = match self {
+impl From<JsonPayloadError> for AppError {
+    fn from(e: JsonPayloadError) -> Self {
+        match e {
+            JsonPayloadError::OverflowKnownLength { .. } | JsonPayloadError::Overflow { .. } => {
+                Self::PayloadTooLarge(e.to_string())
+            }
+            JsonPayloadError::ContentType => {
+                Self::UnsupportedMediaType("Expected an application/json body".to_string())
+            }
+            JsonPayloadError::Serialize(e) => Self::Internal(e.to_string()),
+            e => Self::Validation(e.to_string()),
+        }
+    }
+}
+
+impl From<PathError> for AppError {
+    fn from(e: PathError) -> Self {
+        Self::Validation(e.to_string())
+    }
+}
+
+impl From<QueryPayloadError> for AppError {
+    fn from(e: QueryPayloadError) -> Self {
+        Self::Validation(e.to_string())
+    }
+}
+
+// RFC 7807 body of every error response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    // the last part of `type`, for clients that would rather match on a short string
+    pub code: String,
+    // as sent in the X-Request-Id header, to find the request in the logs,
+    // filled in by the api once the response is on its way out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl AppError {
+    // stable, part of the api like the status code
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Config(_) => "configuration_error",
+            Self::Validation(_) => "invalid_request",
+            Self::NotFound(_) => "not_found",
+            Self::MethodNotAllowed(..) => "method_not_allowed",
+            Self::Unauthorized(_) => "unauthorized",
+            Self::PayloadTooLarge(_) => "payload_too_large",
+            Self::UnsupportedMediaType(_) => "unsupported_media_type",
+            Self::IdempotencyKeyReused(_) => "idempotency_key_reused",
+            Self::Render(_) => "render_failed",
+            Self::Internal(_) => "internal_error",
+            Self::Overloaded(o) => o.reason.as_str(),
+        }
+    }
+
+    pub fn problem_type(&self) -> String {
+        format!("{}{}", PROBLEM_TYPE_BASE, self.code())
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            Self::Config(_) => "Configuration error",
+            Self::Validation(_) => "Invalid request",
+            Self::NotFound(_) => "Not found",
+            Self::MethodNotAllowed(..) => "Method not allowed",
+            Self::Unauthorized(_) => "Unauthorized",
+            Self::PayloadTooLarge(_) => "Payload too large",
+            Self::UnsupportedMediaType(_) => "Unsupported media type",
+            Self::IdempotencyKeyReused(_) => "Idempotency key reused",
+            Self::Render(_) => "Rendering failed",
+            Self::Internal(_) => "Internal error",
+            Self::Overloaded(_) => "Service overloaded",
+        }
+    }
+
+    fn detail(&self) -> String {
+        match self {
+            // details of our own failures stay in the logs
+            Self::Config(_) | Self::Internal(_) => {
+                "The request could not be completed, see the logs for this request id".to_string()
+            }
+            Self::Validation(e)
+            | Self::NotFound(e)
+            | Self::MethodNotAllowed(e, _)
+            | Self::Unauthorized(e)
+            | Self::PayloadTooLarge(e)
+            | Self::UnsupportedMediaType(e)
+            | Self::IdempotencyKeyReused(e)
+            | Self::Render(e) => e.clone(),
+            Self::Overloaded(_) => self.to_string(),
+        }
+    }
+
+    pub fn problem(&self) -> Problem {
+        Problem {
+            problem_type: self.problem_type(),
+            title: self.title().to_string(),
+            status: self.status_code().as_u16(),
+            detail: self.detail(),
+            code: self.code().to_string(),
+            request_id: None,
+        }
+    }
+}
+
+impl ResponseError for AppError {
+    fn status_code(&self) -> StatusCode {
+        match self {
             AppError::Validation(_) => StatusCode::BAD_REQUEST,
-            _ => StatusCode::INTERNAL_SERVER_ERROR,
-        };
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::MethodNotAllowed(..) => StatusCode::METHOD_NOT_ALLOWED,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            AppError::IdempotencyKeyReused(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::Overloaded(o) => match o.reason {
+                OverloadReason::TooManyBatches => StatusCode::TOO_MANY_REQUESTS,
+                OverloadReason::Saturated => StatusCode::SERVICE_UNAVAILABLE,
+            },
+            AppError::Config(_) | AppError::Render(_) | AppError::Internal(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    fn error_response(&self) -> actix_web::HttpResponse {
+        let problem = self.problem();
+        if self.status_code().is_server_error() {
+            error!(code = %problem.code, error = %self, "Request failed");
+        }
+
+        let mut response = actix_web::HttpResponse::build(self.status_code());
+        response.content_type(PROBLEM_CONTENT_TYPE);
+        if let AppError::Unauthorized(_) = self {
+            response.insert_header((actix_web::http::header::WWW_AUTHENTICATE, "Bearer"));
+        }
+        if let AppError::MethodNotAllowed(_, allowed) = self {
+            let allowed: Vec<&str> = allowed.iter().map(Method::as_str).collect();
+            response.insert_header((actix_web::http::header::ALLOW, allowed.join(", ")));
+        }
+        if let AppError::Overloaded(o) = self {
+            // whole seconds, at least one
+            let secs = o.retry_after.as_secs_f64().ceil().max(1.0) as u64;
+            response.insert_header((actix_web::http::header::RETRY_AFTER, secs.to_string()));
+        }
 
-        actix_web::HttpResponse::build(status).json(serde_json::json!({
-            "error": self.to_string()
-        }))
+        response.body(serde_json::to_string(&problem).unwrap_or_default())
     }
 }
@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+
+use actix_web::http::header::{LanguageTag, Preference};
+use serde::Deserialize;
+
+use crate::domain::{ErrorCode, ValidationError};
+
+// messages are written in English at the source, so it needs no catalogue
+pub const DEFAULT_LOCALE: &str = "en";
+
+// key for the message used when there's no field-specific one
+const ANY_FIELD: &str = "_";
+
+const BUILTIN: &[(&str, &str)] = &[
+    ("de", include_str!("messages/de.json")),
+    ("ja", include_str!("messages/ja.json")),
+];
+
+#[derive(Debug, Default, Deserialize)]
+struct Catalog {
+    // display names substituted for `{field}`
+    #[serde(default)]
+    fields: HashMap<String, String>,
+    // message templates by error code, then field or `_`
+    #[serde(default)]
+    messages: HashMap<ErrorCode, HashMap<String, String>>,
+}
+
+// translations of validation messages, keyed by locale (lowercase BCP 47 tag)
+pub struct MessageCatalog {
+    locales: HashMap<String, Catalog>,
+}
+
+impl MessageCatalog {
+    pub fn builtin() -> Self {
+        let locales = BUILTIN
+            .iter()
+            .map(|(locale, json)| {
+                let catalog = serde_json::from_str(json)
+                    .unwrap_or_else(|e| panic!("built-in {} catalogue is invalid: {}", locale, e));
+                (locale.to_string(), catalog)
+            })
+            .collect();
+
+        Self { locales }
+    }
+
+    pub fn supports(&self, locale: &str) -> bool {
+        locale == DEFAULT_LOCALE || self.locales.contains_key(locale)
+    }
+
+    // pick the best supported locale for ranked Accept-Language preferences
+    // a region specific tag falls back to its language, e.g. de-CH to de
+    pub fn negotiate(&self, ranked: &[Preference<LanguageTag>]) -> String {
+        for preference in ranked {
+            let Preference::Specific(tag) = preference else {
+                return DEFAULT_LOCALE.to_string();
+            };
+
+            let full = tag.as_str().to_ascii_lowercase();
+            if self.supports(&full) {
+                return full;
+            }
+
+            let primary = tag.primary_language().to_ascii_lowercase();
+            if self.supports(&primary) {
+                return primary;
+            }
+        }
+
+        DEFAULT_LOCALE.to_string()
+    }
+
+    // rewrite messages into `locale`, keeping the original where there's no translation
+    pub fn localize(&self, locale: &str, errors: &mut [ValidationError]) {
+        for error in errors {
+            if let Some(message) = self.message(locale, error) {
+                error.message = message;
+            }
+        }
+    }
+
+    pub fn message(&self, locale: &str, error: &ValidationError) -> Option<String> {
+        self.fallback_chain(locale)
+            .find_map(|catalog| self.format(catalog, error))
+    }
+
+    // de-ch, then de
+    fn fallback_chain<'a>(&'a self, locale: &str) -> impl Iterator<Item = &'a Catalog> {
+        let locale = locale.to_ascii_lowercase();
+        let primary = locale.split('-').next().unwrap_or_default().to_string();
+        let mut chain = vec![locale];
+        if chain[0] != primary {
+            chain.push(primary);
+        }
+
+        chain.into_iter().filter_map(|l| self.locales.get(&l))
+    }
+
+    fn format(&self, catalog: &Catalog, error: &ValidationError) -> Option<String> {
+        let templates = catalog.messages.get(&error.code)?;
+        let template = templates
+            .get(&error.field)
+            .or_else(|| templates.get(ANY_FIELD))?;
+
+        let field = catalog
+            .fields
+            .get(&error.field)
+            .map(String::as_str)
+            .unwrap_or(&error.field);
+
+        // one pass over the template, so values are never substituted into themselves
+        let mut message = String::with_capacity(template.len());
+        let mut rest = template.as_str();
+        while let Some(start) = rest.find('{') {
+            message.push_str(&rest[..start]);
+            let end = start + rest[start..].find('}')?;
+            let name = &rest[start + 1..end];
+            // a placeholder the error has no value for, better to show the original
+            let value = match name {
+                "field" => field,
+                _ => error.params.get(name)?,
+            };
+            message.push_str(value);
+            rest = &rest[end + 1..];
+        }
+        message.push_str(rest);
+
+        Some(message)
+    }
+}
+
+impl Default for MessageCatalog {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::header::{AcceptLanguage, Header};
+    use actix_web::test::TestRequest;
+
+    fn ranked(header: &str) -> Vec<Preference<LanguageTag>> {
+        let req = TestRequest::default()
+            .insert_header(("Accept-Language", header))
+            .to_http_request();
+        AcceptLanguage::parse(&req).unwrap().ranked()
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let catalog = MessageCatalog::builtin();
+
+        assert_eq!(
+            catalog.negotiate(&ranked("de-CH, de;q=0.9, en;q=0.8")),
+            "de"
+        );
+        assert_eq!(catalog.negotiate(&ranked("fr-FR, ja;q=0.5")), "ja");
+        assert_eq!(catalog.negotiate(&ranked("fr-FR, *;q=0.5")), "en");
+        assert_eq!(catalog.negotiate(&ranked("fr")), "en");
+        assert_eq!(catalog.negotiate(&[]), "en");
+    }
+
+    #[test]
+    fn test_localize() {
+        let catalog = MessageCatalog::builtin();
+        let mut errors = vec![
+            ValidationError::new(
+                "name",
+                "Name too long (max 100 characters)",
+                ErrorCode::TooLong,
+            )
+            .with_param("max", 100),
+            ValidationError::new("email", "Invalid email format", ErrorCode::InvalidFormat),
+            // missing the params the template needs
+            ValidationError::new("html", "Rendered HTML is too big", ErrorCode::TooLarge),
+        ];
+
+        catalog.localize("de-AT", &mut errors);
+        assert_eq!(errors[0].message, "Name ist zu lang (maximal 100 Zeichen)");
+        assert_eq!(errors[1].message, "Ungültiges E-Mail-Format");
+        assert_eq!(errors[2].message, "Rendered HTML is too big");
+
+        let mut errors = vec![ValidationError::new(
+            "name",
+            "Name is required",
+            ErrorCode::Required,
+        )];
+        catalog.localize("en", &mut errors);
+        assert_eq!(errors[0].message, "Name is required");
+
+        catalog.localize("ja", &mut errors);
+        assert_eq!(errors[0].message, "氏名は必須です");
+
+        // values are inserted as they are, braces and all
+        let mut errors = vec![
+            ValidationError::new("company", "Unknown company", ErrorCode::NotInDirectory)
+                .with_param("value", "{field} {x}"),
+            ValidationError::new("html.a", "Link text \"here\"", ErrorCode::AmbiguousLinkText)
+                .with_param("text", "here"),
+        ];
+        catalog.localize("de", &mut errors);
+        assert_eq!(
+            errors[0].message,
+            "Unbekannter Wert „{field} {x}“ für Firma"
+        );
+        assert_eq!(
+            errors[1].message,
+            "Linktext „here“ beschreibt das Ziel nicht"
+        );
+    }
+}
@@ -0,0 +1,3 @@
+pub mod catalog;
+
+pub use catalog::{DEFAULT_LOCALE, MessageCatalog};
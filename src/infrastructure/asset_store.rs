@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+use crate::domain::AssetStore;
+use crate::domain::assets::Asset;
+
+#[derive(Default)]
+pub struct InMemoryAssetStore {
+    assets: RwLock<HashMap<String, (Asset, Vec<u8>)>>,
+}
+
+impl InMemoryAssetStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl AssetStore for InMemoryAssetStore {
+    fn get(&self, id: &str) -> Option<Asset> {
+        self.assets
+            .read()
+            .unwrap()
+            .get(id)
+            .map(|(asset, _)| asset.clone())
+    }
+
+    fn get_content(&self, id: &str) -> Option<(Asset, Vec<u8>)> {
+        self.assets.read().unwrap().get(id).cloned()
+    }
+
+    fn put(&self, asset: Asset, content: Vec<u8>) -> anyhow::Result<()> {
+        self.assets
+            .write()
+            .unwrap()
+            .insert(asset.id.clone(), (asset, content));
+        Ok(())
+    }
+}
+
+// stores each asset as `<id>.<ext>` next to a `<id>.json` metadata file
+// the metadata index is rebuilt from the directory on startup
+pub struct FileAssetStore {
+    dir: PathBuf,
+    index: RwLock<HashMap<String, Asset>>,
+}
+
+impl FileAssetStore {
+    pub fn open(dir: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+
+        let mut index = HashMap::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+
+            let asset: Asset = serde_json::from_slice(&std::fs::read(&path)?)?;
+            index.insert(asset.id.clone(), asset);
+        }
+
+        Ok(Self {
+            dir,
+            index: RwLock::new(index),
+        })
+    }
+
+    fn content_path(&self, asset: &Asset) -> PathBuf {
+        self.dir
+            .join(format!("{}.{}", asset.id, asset.format.extension()))
+    }
+}
+
+impl AssetStore for FileAssetStore {
+    fn get(&self, id: &str) -> Option<Asset> {
+        self.index.read().unwrap().get(id).cloned()
+    }
+
+    fn get_content(&self, id: &str) -> Option<(Asset, Vec<u8>)> {
+        let asset = self.get(id)?;
+        let content = std::fs::read(self.content_path(&asset)).ok()?;
+        Some((asset, content))
+    }
+
+    fn put(&self, asset: Asset, content: Vec<u8>) -> anyhow::Result<()> {
+        // content first, so the index never points at a missing file
+        std::fs::write(self.content_path(&asset), &content)?;
+        std::fs::write(
+            self.dir.join(format!("{}.json", asset.id)),
+            serde_json::to_vec(&asset)?,
+        )?;
+
+        self.index.write().unwrap().insert(asset.id.clone(), asset);
+        Ok(())
+    }
+}
@@ -1,11 +1,41 @@
 use config::{Config as ConfigLoader, File};
 use serde::Deserialize;
 
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+use crate::domain::{AssetRules, RulesConfig};
+use crate::infrastructure::webhook::WebhookConfig;
+use crate::pipeline::manager::DEFAULT_STAGES;
+use crate::pipeline::revalidation::parse_schedule;
+use crate::pipeline::stages::STAGE_NAMES;
+use crate::pipeline::{AdmissionLimits, Deadlines, JobSettings};
+use crate::rendering::SizeBudget;
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
     pub server: ServerConfig,
     pub pipeline: PipelineConfig,
     pub observability: ObservabilityConfig,
+    pub links: LinksConfig,
+    #[serde(default)]
+    pub templates: TemplatesConfig,
+    #[serde(default)]
+    pub assets: AssetsConfig,
+    #[serde(default)]
+    pub rendering: RenderingConfig,
+    #[serde(default)]
+    pub tenants: TenantsConfig,
+    #[serde(default)]
+    pub directory: DirectoryConfig,
+    #[serde(default)]
+    pub rules: RulesConfig,
+    #[serde(default)]
+    pub idempotency: IdempotencyConfig,
+    #[serde(default)]
+    pub webhooks: WebhookConfig,
+    #[serde(default)]
+    pub revalidation: RevalidationConfig,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -18,7 +48,48 @@ pub struct ServerConfig {
 #[derive(Debug, Clone, Deserialize)]
 pub struct PipelineConfig {
     pub workers: usize,
+    // batch jobs waiting on the workers before further ones are held back
+    #[serde(default = "default_queue_depth")]
+    pub queue_depth: usize,
     pub max_batch_size: usize,
+    #[serde(default)]
+    pub cache: CacheConfig,
+    // built-in stages run for each signature, in order
+    #[serde(default = "default_stages")]
+    pub stages: Vec<String>,
+    #[serde(default)]
+    pub backpressure: AdmissionLimits,
+    #[serde(default)]
+    pub deadlines: Deadlines,
+    #[serde(default)]
+    pub jobs: JobSettings,
+}
+
+fn default_queue_depth() -> usize {
+    16
+}
+
+fn default_stages() -> Vec<String> {
+    DEFAULT_STAGES.iter().map(|s| s.to_string()).collect()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CacheConfig {
+    // reuse results for signatures whose content was already validated
+    pub enabled: bool,
+    pub max_entries: usize,
+    pub ttl_secs: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_entries: 10_000,
+            ttl_secs: 24 * 60 * 60,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -27,6 +98,156 @@ pub struct ObservabilityConfig {
     pub log_format: String,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct LinksConfig {
+    // check that signature links resolve, adds a network round trip per link
+    pub probe: bool,
+    pub probe_timeout_ms: u64,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TemplatesConfig {
+    // directory of `<uuid>.hbs` files registered on top of the built-in template
+    pub dir: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AssetsConfig {
+    // directory for uploaded images, kept in memory if unset
+    pub dir: Option<String>,
+    // absolute url assets are served under, used in signatures
+    pub public_base_url: String,
+    // hard limit on upload request bodies
+    pub max_upload_bytes: usize,
+    pub logo: AssetRules,
+    pub photo: AssetRules,
+    pub banner: AssetRules,
+}
+
+impl Default for AssetsConfig {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            public_base_url: "https://localhost:8080/assets".to_string(),
+            max_upload_bytes: 5 * 1024 * 1024,
+            logo: AssetRules {
+                max_bytes: 50 * 1024,
+                min_width: 16,
+                max_width: 600,
+                min_height: 16,
+                max_height: 300,
+                min_aspect_ratio: 0.5,
+                max_aspect_ratio: 6.0,
+            },
+            photo: AssetRules {
+                max_bytes: 100 * 1024,
+                min_width: 64,
+                max_width: 512,
+                min_height: 64,
+                max_height: 512,
+                min_aspect_ratio: 0.75,
+                max_aspect_ratio: 1.34,
+            },
+            banner: AssetRules {
+                max_bytes: 150 * 1024,
+                min_width: 200,
+                max_width: 700,
+                min_height: 40,
+                max_height: 300,
+                min_aspect_ratio: 2.0,
+                max_aspect_ratio: 10.0,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct IdempotencyConfig {
+    // how long a response is replayed for retries with the same `Idempotency-Key`
+    pub ttl_secs: u64,
+    pub max_entries: usize,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            ttl_secs: 24 * 60 * 60,
+            max_entries: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DirectoryConfig {
+    // json or csv file of canonical company names and job titles
+    pub path: Option<String>,
+    // how often the file is checked for changes
+    pub reload_interval_secs: u64,
+}
+
+impl Default for DirectoryConfig {
+    fn default() -> Self {
+        Self {
+            path: None,
+            reload_interval_secs: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RevalidationConfig {
+    // cron expression for re-running the rules over stored signatures, e.g. "0 3 * * *"
+    pub schedule: Option<String>,
+    // signatures run through the pipeline at a time
+    pub chunk_size: usize,
+    // file statuses and the last report are kept in across restarts, in memory if unset
+    pub state: Option<String>,
+}
+
+impl Default for RevalidationConfig {
+    fn default() -> Self {
+        Self {
+            schedule: None,
+            chunk_size: 500,
+            state: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RenderingConfig {
+    pub budget: SizeBudget,
+    // overrides keyed by tenant, the one the request's api key belongs to
+    pub tenant_budgets: HashMap<String, SizeBudget>,
+}
+
+impl RenderingConfig {
+    pub fn budget_for(&self, tenant: Option<&str>) -> &SizeBudget {
+        tenant
+            .and_then(|t| self.tenant_budgets.get(t))
+            .unwrap_or(&self.budget)
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TenantsConfig {
+    // tenant keyed by the hex sha-256 of its api key, sent as `Authorization: Bearer <key>`
+    pub api_keys: HashMap<String, String>,
+}
+
+impl TenantsConfig {
+    pub fn tenant_for_key(&self, key: &str) -> Option<&str> {
+        let digest = hex::encode(Sha256::digest(key.as_bytes()));
+        self.api_keys.get(&digest).map(String::as_str)
+    }
+}
+
 impl Config {
     pub fn load() -> anyhow::Result<Self> {
         let config = ConfigLoader::builder()
@@ -38,6 +259,8 @@ impl Config {
             .set_default("pipeline.max_batch_size", 1000)?
             .set_default("observability.log_level", "info")?
             .set_default("observability.log_format", "json")?
+            .set_default("links.probe", false)?
+            .set_default("links.probe_timeout_ms", 3000)?
             .add_source(File::with_name("config").required(false)) // add config from external source (file)
             .build()?;
         Ok(config.try_deserialize()?)
@@ -52,9 +275,76 @@ impl Config {
             anyhow::bail!("Server workers cannot be 0");
         }
 
+        if self.pipeline.workers == 0 {
+            anyhow::bail!("Pipeline workers cannot be 0");
+        }
+
         if self.pipeline.max_batch_size == 0 {
             anyhow::bail!("Max batch size cannot be 0");
         }
+
+        if let Some(stage) = self
+            .pipeline
+            .stages
+            .iter()
+            .find(|stage| !STAGE_NAMES.contains(&stage.as_str()))
+        {
+            anyhow::bail!("Unknown pipeline stage {}", stage);
+        }
+
+        if self.pipeline.backpressure.max_in_flight_signatures < self.pipeline.max_batch_size {
+            anyhow::bail!("Max in-flight signatures cannot be below the max batch size");
+        }
+
+        if self.pipeline.backpressure.max_queued_batches == 0 {
+            anyhow::bail!("Max queued batches cannot be 0");
+        }
+
+        if self.pipeline.deadlines.signature_ms == 0 || self.pipeline.deadlines.batch_ms == 0 {
+            anyhow::bail!("Pipeline deadlines cannot be 0");
+        }
+
+        if self.pipeline.jobs.chunk_size == 0 {
+            anyhow::bail!("Job chunk size cannot be 0");
+        }
+
+        if self.pipeline.jobs.max_attempts == 0 {
+            anyhow::bail!("Job max attempts cannot be 0");
+        }
+
+        if self.pipeline.cache.enabled && self.pipeline.cache.max_entries == 0 {
+            anyhow::bail!("Validation cache size cannot be 0");
+        }
+
+        if !self.assets.public_base_url.starts_with("https://") {
+            anyhow::bail!("Asset public base url must be an https url");
+        }
+
+        if self.directory.path.is_some() && self.directory.reload_interval_secs == 0 {
+            anyhow::bail!("Directory reload interval cannot be 0");
+        }
+
+        self.rules.build()?;
+
+        if self.idempotency.max_entries == 0 {
+            anyhow::bail!("Idempotency cache size cannot be 0");
+        }
+
+        if self.webhooks.max_attempts == 0 || self.webhooks.timeout_ms == 0 {
+            anyhow::bail!("Webhook attempts and timeout cannot be 0");
+        }
+
+        if let Some(schedule) = &self.revalidation.schedule {
+            parse_schedule(schedule)?;
+        }
+
+        if self.revalidation.chunk_size == 0 {
+            anyhow::bail!("Revalidation chunk size cannot be 0");
+        }
+
+        if self.links.probe && self.links.probe_timeout_ms == 0 {
+            anyhow::bail!("Link probe timeout cannot be 0");
+        }
         Ok(())
     }
 }
@@ -0,0 +1,148 @@
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use url::{Host, Url};
+
+// looks up the addresses of a host name
+#[async_trait]
+pub trait Lookup: Send + Sync {
+    async fn lookup(&self, host: &str) -> io::Result<Vec<IpAddr>>;
+}
+
+pub struct SystemLookup;
+
+#[async_trait]
+impl Lookup for SystemLookup {
+    async fn lookup(&self, host: &str) -> io::Result<Vec<IpAddr>> {
+        Ok(tokio::net::lookup_host((host, 0))
+            .await?
+            .map(|addr| addr.ip())
+            .collect())
+    }
+}
+
+// where requests to urls taken from clients may go: absolute http(s) urls on public addresses,
+// so a client can't have this service reach into its own network or a cloud metadata endpoint.
+// also the resolver of the clients sending them, so the address connected to is one that
+// was checked, not whatever the name resolves to a moment later
+#[derive(Clone)]
+pub struct Destinations {
+    // hosts allowed even though they resolve to a private or loopback address
+    allowed_hosts: Arc<Vec<String>>,
+    lookup: Arc<dyn Lookup>,
+}
+
+impl Destinations {
+    pub fn new(allowed_hosts: Vec<String>) -> Self {
+        Self {
+            allowed_hosts: Arc::new(allowed_hosts),
+            lookup: Arc::new(SystemLookup),
+        }
+    }
+
+    pub fn with_lookup(mut self, lookup: Arc<dyn Lookup>) -> Self {
+        self.lookup = lookup;
+        self
+    }
+
+    pub async fn check(&self, url: &str) -> Result<(), String> {
+        let parsed = Url::parse(url).map_err(|e| e.to_string())?;
+        self.check_url(&parsed)?;
+        match parsed.host() {
+            Some(Host::Domain(domain)) if !self.allowed(domain) => {
+                self.public_addresses(domain).await.map(|_| ())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // what can be told without a lookup, e.g. for each redirect: the scheme and hosts given
+    // as addresses, names are checked once they are resolved
+    pub fn check_url(&self, url: &Url) -> Result<(), String> {
+        let Some(host) = url.host() else {
+            return Err("not an http or https url".to_string());
+        };
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err("not an http or https url".to_string());
+        }
+
+        let name = url.host_str().unwrap_or_default();
+        if self.allowed(name) {
+            return Ok(());
+        }
+        match host {
+            Host::Ipv4(ip) => require_public(name, &[IpAddr::V4(ip)]),
+            Host::Ipv6(ip) => require_public(name, &[IpAddr::V6(ip)]),
+            Host::Domain(_) => Ok(()),
+        }
+    }
+
+    fn allowed(&self, host: &str) -> bool {
+        self.allowed_hosts
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(host))
+    }
+
+    async fn public_addresses(&self, domain: &str) -> Result<Vec<IpAddr>, String> {
+        let addresses = self
+            .lookup
+            .lookup(domain)
+            .await
+            .map_err(|e| format!("cannot resolve host {}: {}", domain, e))?;
+        require_public(domain, &addresses)?;
+        Ok(addresses)
+    }
+}
+
+impl Resolve for Destinations {
+    fn resolve(&self, name: Name) -> Resolving {
+        let destinations = self.clone();
+        Box::pin(async move {
+            let host = name.as_str();
+            let addresses = if destinations.allowed(host) {
+                destinations.lookup.lookup(host).await?
+            } else {
+                destinations.public_addresses(host).await?
+            };
+            // the connector fills in the port of the url
+            let addrs: Addrs = Box::new(addresses.into_iter().map(|ip| SocketAddr::new(ip, 0)));
+            Ok(addrs)
+        })
+    }
+}
+
+fn require_public(host: &str, addresses: &[IpAddr]) -> Result<(), String> {
+    if addresses.is_empty() || !addresses.iter().all(|&ip| is_public(ip)) {
+        return Err(format!("host {} is not a public address", host));
+    }
+    Ok(())
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                // includes 169.254.169.254, the metadata endpoint of most clouds
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                // carrier-grade nat, 100.64.0.0/10
+                || (a == 100 && (b & 0xc0) == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local())
+            }
+        },
+    }
+}
@@ -0,0 +1,145 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::StatusCode;
+use reqwest::redirect::Policy;
+use url::Url;
+
+use crate::domain::{LinkProber, ProbeOutcome};
+use crate::infrastructure::destination::Destinations;
+
+// redirects followed before a link counts as unreachable
+const MAX_REDIRECTS: usize = 10;
+
+// probes links with a HEAD request, falling back to GET for servers that reject HEAD.
+// links come from clients, so only public addresses are probed, redirects included
+pub struct HttpProber {
+    client: reqwest::Client,
+    destinations: Destinations,
+}
+
+impl HttpProber {
+    pub fn new(timeout: Duration) -> anyhow::Result<Self> {
+        Self::with_destinations(timeout, Destinations::new(Vec::new()))
+    }
+
+    pub fn with_destinations(
+        timeout: Duration,
+        destinations: Destinations,
+    ) -> anyhow::Result<Self> {
+        let hops = destinations.clone();
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .user_agent(concat!("email-processor/", env!("CARGO_PKG_VERSION")))
+            // names are checked by the resolver, addresses and schemes here for each hop
+            .redirect(Policy::custom(move |attempt| {
+                if attempt.previous().len() >= MAX_REDIRECTS {
+                    return attempt.error("too many redirects");
+                }
+                match hops.check_url(attempt.url()) {
+                    Ok(()) => attempt.follow(),
+                    Err(e) => attempt.error(e),
+                }
+            }))
+            .dns_resolver(Arc::new(destinations.clone()))
+            .build()?;
+
+        Ok(Self {
+            client,
+            destinations,
+        })
+    }
+}
+
+#[async_trait]
+impl LinkProber for HttpProber {
+    async fn probe(&self, url: &Url) -> ProbeOutcome {
+        if let Err(e) = self.destinations.check_url(url) {
+            return ProbeOutcome::Unreachable(format!("not probed, {}", e));
+        }
+
+        let mut response = self.client.head(url.clone()).send().await;
+
+        if let Ok(resp) = &response
+            && matches!(
+                resp.status(),
+                StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+            )
+        {
+            response = self.client.get(url.clone()).send().await;
+        }
+
+        // the error itself is left out, it may tell about hosts the client can't see
+        match response {
+            Ok(resp) if resp.status().is_success() => ProbeOutcome::Reachable,
+            Ok(resp) => ProbeOutcome::Unreachable(format!("HTTP {}", resp.status().as_u16())),
+            Err(e) if e.is_timeout() => ProbeOutcome::Unreachable("timed out".to_string()),
+            Err(e) if e.is_redirect() => {
+                ProbeOutcome::Unreachable("redirected to a url that isn't probed".to_string())
+            }
+            Err(e) if e.is_connect() => ProbeOutcome::Unreachable("could not connect".to_string()),
+            Err(_) => ProbeOutcome::Unreachable("request failed".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{ErrorKind, Read, Write};
+    use std::net::TcpListener;
+
+    #[actix_web::test]
+    async fn test_private_addresses_not_probed() {
+        let internal = TcpListener::bind("127.0.0.1:0").unwrap();
+        internal.set_nonblocking(true).unwrap();
+        let internal_url = format!(
+            "http://127.0.0.1:{}/",
+            internal.local_addr().unwrap().port()
+        );
+
+        // stands in for a public site redirecting to an internal one
+        let public = TcpListener::bind("127.0.0.2:0").unwrap();
+        let public_url = format!("http://127.0.0.2:{}/", public.local_addr().unwrap().port());
+        let redirect = format!(
+            "HTTP/1.1 302 Found\r\nLocation: {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            internal_url
+        );
+        std::thread::spawn(move || {
+            let (mut stream, _) = public.accept().unwrap();
+            let _ = stream.read(&mut [0; 1024]);
+            stream.write_all(redirect.as_bytes()).unwrap();
+        });
+
+        let prober = HttpProber::with_destinations(
+            Duration::from_secs(5),
+            Destinations::new(vec!["127.0.0.2".to_string()]),
+        )
+        .unwrap();
+
+        let probe = |url: String| {
+            let prober = &prober;
+            async move {
+                match prober.probe(&Url::parse(&url).unwrap()).await {
+                    ProbeOutcome::Unreachable(reason) => reason,
+                    ProbeOutcome::Reachable => panic!("{} probed as reachable", url),
+                }
+            }
+        };
+        assert_eq!(
+            probe(internal_url).await,
+            "not probed, host 127.0.0.1 is not a public address"
+        );
+        assert_eq!(
+            probe(public_url).await,
+            "redirected to a url that isn't probed"
+        );
+        // resolved to loopback
+        assert_eq!(
+            probe("http://localhost/".to_string()).await,
+            "could not connect"
+        );
+        assert_eq!(internal.accept().unwrap_err().kind(), ErrorKind::WouldBlock);
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use actix_web::http::{StatusCode, header::HeaderMap};
+use actix_web::web::Bytes;
+use tokio::sync::OnceCell;
+
+// a response kept so a retried request can be answered without redoing the work
+#[derive(Debug, Clone)]
+pub struct CachedResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+// filled once by the first request with a given key, concurrent retries wait on it
+pub type ResponseSlot = Arc<OnceCell<CachedResponse>>;
+
+// sha-256 of what a key was first used for, a retry has to send the same request
+pub type Fingerprint = [u8; 32];
+
+struct Entry {
+    created_at: Instant,
+    fingerprint: Fingerprint,
+    slot: ResponseSlot,
+}
+
+#[derive(Default)]
+struct Entries {
+    by_key: HashMap<String, Entry>,
+    // keys oldest first, a key dropped and used again leaves a stale record behind
+    order: VecDeque<(Instant, String)>,
+}
+
+impl Entries {
+    // drop the oldest record and its entry, unless the entry has been replaced since
+    fn pop_oldest(&mut self) {
+        if let Some((created_at, key)) = self.order.pop_front()
+            && self
+                .by_key
+                .get(&key)
+                .is_some_and(|entry| entry.created_at == created_at)
+        {
+            self.by_key.remove(&key);
+        }
+    }
+}
+
+pub struct IdempotencyCache {
+    ttl: Duration,
+    max_entries: usize,
+    entries: Mutex<Entries>,
+}
+
+impl IdempotencyCache {
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            ttl,
+            max_entries,
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    // the slot for a key, a fresh one if the key is new or its response has expired,
+    // none if the key was used for a different request
+    pub fn slot(&self, key: &str, fingerprint: Fingerprint) -> Option<ResponseSlot> {
+        let mut entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+        while let Some((created_at, _)) = entries.order.front()
+            && now.duration_since(*created_at) >= self.ttl
+        {
+            entries.pop_oldest();
+        }
+
+        if let Some(entry) = entries.by_key.get(key) {
+            return (entry.fingerprint == fingerprint).then(|| entry.slot.clone());
+        }
+
+        while entries.by_key.len() >= self.max_entries.max(1) && !entries.order.is_empty() {
+            entries.pop_oldest();
+        }
+
+        let slot = ResponseSlot::default();
+        entries.order.push_back((now, key.to_string()));
+        entries.by_key.insert(
+            key.to_string(),
+            Entry {
+                created_at: now,
+                fingerprint,
+                slot: slot.clone(),
+            },
+        );
+        Some(slot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(body: &'static str) -> CachedResponse {
+        CachedResponse {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: Bytes::from_static(body.as_bytes()),
+        }
+    }
+
+    const SAME: Fingerprint = [0; 32];
+
+    #[test]
+    fn test_slot_shared_until_expiry() {
+        let cache = IdempotencyCache::new(Duration::from_millis(50), 10);
+        cache
+            .slot("a", SAME)
+            .unwrap()
+            .set(response("first"))
+            .unwrap();
+        assert_eq!(cache.slot("a", SAME).unwrap().get().unwrap().body, "first");
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(cache.slot("a", SAME).unwrap().get().is_none());
+    }
+
+    #[test]
+    fn test_oldest_evicted_at_capacity() {
+        let cache = IdempotencyCache::new(Duration::from_secs(60), 2);
+        cache.slot("a", SAME).unwrap().set(response("a")).unwrap();
+        cache.slot("b", SAME).unwrap().set(response("b")).unwrap();
+        cache.slot("c", SAME).unwrap().set(response("c")).unwrap();
+
+        assert!(cache.slot("b", SAME).unwrap().get().is_some());
+        assert!(cache.slot("a", SAME).unwrap().get().is_none());
+    }
+
+    #[test]
+    fn test_key_bound_to_request() {
+        let cache = IdempotencyCache::new(Duration::from_secs(60), 10);
+        cache.slot("a", SAME).unwrap().set(response("a")).unwrap();
+        assert!(cache.slot("a", [1; 32]).is_none());
+        assert!(cache.slot("a", SAME).is_some());
+    }
+}
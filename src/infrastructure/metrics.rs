@@ -1,4 +1,4 @@
-use metrics::{counter, describe_counter, describe_histogram, histogram};
+use metrics::{counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram};
 use metrics_exporter_prometheus::PrometheusBuilder;
 use std::net::SocketAddr;
 
@@ -11,6 +11,39 @@ pub fn init_metrics(addr: SocketAddr) -> Result<(), Box<dyn std::error::Error +
     describe_counter!("http_requests_total", "Total HTTP requests");
     describe_histogram!("http_request_duration_seconds", "HTTP request duration");
     describe_counter!("signatures_validated_total", "Total signatures validated");
+    describe_gauge!(
+        "pipeline_in_flight_signatures",
+        "Signatures currently being processed"
+    );
+    describe_gauge!(
+        "pipeline_queued_batches",
+        "Batches accepted and not finished yet"
+    );
+    describe_counter!(
+        "pipeline_rejections_total",
+        "Requests turned away because the pipeline was full"
+    );
+    describe_histogram!(
+        "pipeline_stage_duration_seconds",
+        "Time spent in each pipeline stage per batch"
+    );
+    describe_counter!("pipeline_jobs_total", "Batch jobs finished, by status");
+    describe_gauge!(
+        "stored_signatures_failing",
+        "Stored signatures invalid under the current rules, as of the last revalidation"
+    );
+    describe_counter!(
+        "job_webhook_deliveries_total",
+        "Job callback attempts, by result"
+    );
+    describe_counter!(
+        "pipeline_job_dead_letters_total",
+        "Signatures given up on after failing every attempt"
+    );
+    describe_counter!(
+        "validation_cache_requests_total",
+        "Validation cache lookups by result"
+    );
 
     Ok(())
 }
@@ -41,3 +74,44 @@ pub fn record_validation(valid: bool) {
     )
     .increment(1);
 }
+
+pub fn record_cache_hit() {
+    counter!("validation_cache_requests_total", "result" => "hit").increment(1);
+}
+
+pub fn record_cache_miss() {
+    counter!("validation_cache_requests_total", "result" => "miss").increment(1);
+}
+
+pub fn record_stage_duration(stage: &'static str, duration_secs: f64) {
+    histogram!("pipeline_stage_duration_seconds", "stage" => stage).record(duration_secs);
+}
+
+pub fn set_pipeline_depth(in_flight_signatures: usize, queued_batches: usize) {
+    gauge!("pipeline_in_flight_signatures").set(in_flight_signatures as f64);
+    gauge!("pipeline_queued_batches").set(queued_batches as f64);
+}
+
+pub fn record_rejection(reason: &'static str) {
+    counter!("pipeline_rejections_total", "reason" => reason).increment(1);
+}
+
+pub fn record_job_finished(status: &'static str) {
+    counter!("pipeline_jobs_total", "status" => status).increment(1);
+}
+
+pub fn record_dead_letters(count: usize) {
+    counter!("pipeline_job_dead_letters_total").increment(count as u64);
+}
+
+pub fn record_webhook_delivery(delivered: bool) {
+    counter!(
+        "job_webhook_deliveries_total",
+        "result" => if delivered { "delivered" } else { "failed" }
+    )
+    .increment(1);
+}
+
+pub fn set_failing_signatures(count: usize) {
+    gauge!("stored_signatures_failing").set(count as f64);
+}
@@ -1,5 +1,13 @@
+pub mod asset_store;
 pub mod config;
+pub mod destination;
+pub mod http_prober;
+pub mod idempotency;
 pub mod logging;
 pub mod metrics;
+pub mod storage;
+pub mod webhook;
 
+pub use asset_store::{FileAssetStore, InMemoryAssetStore};
 pub use config::Config;
+pub use storage::{InMemorySignatureStore, SignatureStore};
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use uuid::Uuid;
+
+use crate::domain::{EmailSignature, duplicates::normalize_email};
+
+// storage for submitted signatures
+pub trait SignatureStore: Send + Sync {
+    fn get(&self, id: Uuid) -> Option<EmailSignature>;
+    fn put(&self, sig: EmailSignature);
+    fn list(&self) -> Vec<EmailSignature>;
+
+    // a stored signature other than `id` using the same email
+    fn find_other_by_email(&self, email: &str, id: Uuid) -> Option<EmailSignature> {
+        let email = normalize_email(email);
+        self.list()
+            .into_iter()
+            .find(|sig| sig.id != id && normalize_email(&sig.email) == email)
+    }
+}
+
+#[derive(Default)]
+pub struct InMemorySignatureStore {
+    inner: RwLock<Stored>,
+}
+
+#[derive(Default)]
+struct Stored {
+    signatures: HashMap<Uuid, EmailSignature>,
+    // normalized email to the signatures using it, kept in step with `signatures`
+    by_email: HashMap<String, Vec<Uuid>>,
+}
+
+impl InMemorySignatureStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SignatureStore for InMemorySignatureStore {
+    fn get(&self, id: Uuid) -> Option<EmailSignature> {
+        self.inner.read().unwrap().signatures.get(&id).cloned()
+    }
+
+    fn put(&self, sig: EmailSignature) {
+        let mut inner = self.inner.write().unwrap();
+        let email = normalize_email(&sig.email);
+        if let Some(previous) = inner.signatures.get(&sig.id) {
+            let previous = normalize_email(&previous.email);
+            if previous == email {
+                inner.signatures.insert(sig.id, sig);
+                return;
+            }
+            if let Some(ids) = inner.by_email.get_mut(&previous) {
+                ids.retain(|id| *id != sig.id);
+                if ids.is_empty() {
+                    inner.by_email.remove(&previous);
+                }
+            }
+        }
+
+        inner.by_email.entry(email).or_default().push(sig.id);
+        inner.signatures.insert(sig.id, sig);
+    }
+
+    fn list(&self) -> Vec<EmailSignature> {
+        self.inner
+            .read()
+            .unwrap()
+            .signatures
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    fn find_other_by_email(&self, email: &str, id: Uuid) -> Option<EmailSignature> {
+        let inner = self.inner.read().unwrap();
+        let other = inner
+            .by_email
+            .get(&normalize_email(email))?
+            .iter()
+            .find(|other| **other != id)?;
+        inner.signatures.get(other).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_other_by_email() {
+        let store = InMemorySignatureStore::new();
+        let mut sig = EmailSignature::builder().email("Jane@Example.com").build();
+        store.put(sig.clone());
+        let other = Uuid::new_v4();
+        assert_eq!(
+            store
+                .find_other_by_email("jane@example.com", other)
+                .unwrap()
+                .id,
+            sig.id
+        );
+        assert!(
+            store
+                .find_other_by_email("jane@example.com", sig.id)
+                .is_none()
+        );
+
+        // the index follows a changed email
+        sig.email = "jane.doe@example.com".to_string();
+        store.put(sig.clone());
+        assert!(
+            store
+                .find_other_by_email("jane@example.com", other)
+                .is_none()
+        );
+        assert_eq!(
+            store
+                .find_other_by_email("JANE.DOE@example.com", other)
+                .unwrap()
+                .id,
+            sig.id
+        );
+    }
+}
@@ -0,0 +1,263 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::infrastructure::destination::Destinations;
+
+// `sha256=<hex>` HMAC of the raw body, keyed with `webhooks.secret`
+pub const SIGNATURE_HEADER: &str = "X-Signature-256";
+// the same for every attempt at one notification, for receivers to drop repeats
+pub const DELIVERY_HEADER: &str = "X-Webhook-Delivery";
+pub const ATTEMPT_HEADER: &str = "X-Webhook-Attempt";
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    // signs callbacks when set, receivers check the signature header against it
+    pub secret: Option<String>,
+    // where clients reach this service, prefixes the result link sent with a callback
+    pub public_base_url: Option<String>,
+    pub max_attempts: u32,
+    // wait before the first retry, doubled for each one after
+    pub initial_backoff_ms: u64,
+    pub timeout_ms: u64,
+    // hosts callbacks may go to even though they resolve to a private or loopback address
+    pub allowed_hosts: Vec<String>,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            secret: None,
+            public_base_url: None,
+            max_attempts: 5,
+            initial_backoff_ms: 1000,
+            timeout_ms: 5000,
+            allowed_hosts: Vec::new(),
+        }
+    }
+}
+
+// one try at delivering a callback
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeliveryAttempt {
+    pub attempt: u32,
+    pub attempted_at: DateTime<Utc>,
+    // response status, none if the request didn't get one
+    pub status: Option<u16>,
+    pub error: Option<String>,
+    pub delivered: bool,
+}
+
+// posts signed json callbacks
+pub struct WebhookSender {
+    client: reqwest::Client,
+    secret: Option<String>,
+    max_attempts: u32,
+    initial_backoff: Duration,
+    destinations: Destinations,
+}
+
+impl WebhookSender {
+    pub fn new(config: &WebhookConfig) -> anyhow::Result<Self> {
+        Self::with_destinations(config, Destinations::new(config.allowed_hosts.clone()))
+    }
+
+    pub fn with_destinations(
+        config: &WebhookConfig,
+        destinations: Destinations,
+    ) -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .user_agent(concat!("email-processor/", env!("CARGO_PKG_VERSION")))
+            // a redirect could point anywhere, past the destination check
+            .redirect(reqwest::redirect::Policy::none())
+            .dns_resolver(Arc::new(destinations.clone()))
+            .build()?;
+
+        Ok(Self {
+            client,
+            secret: config.secret.clone(),
+            max_attempts: config.max_attempts,
+            initial_backoff: Duration::from_millis(config.initial_backoff_ms),
+            destinations,
+        })
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(1 << attempt.saturating_sub(1).min(16))
+    }
+
+    // a 2xx response counts as delivered, anything else is worth another try
+    pub async fn send(
+        &self,
+        url: &str,
+        delivery: &str,
+        attempt: u32,
+        body: &[u8],
+    ) -> DeliveryAttempt {
+        // checked again for each attempt, and once more by the client's resolver
+        // for the address it connects to
+        if let Err(e) = self.destinations.check(url).await {
+            return DeliveryAttempt {
+                attempt,
+                attempted_at: Utc::now(),
+                status: None,
+                error: Some(format!("Invalid callback url: {}", e)),
+                delivered: false,
+            };
+        }
+
+        let mut request = self
+            .client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(DELIVERY_HEADER, delivery)
+            .header(ATTEMPT_HEADER, attempt.to_string())
+            .body(body.to_vec());
+        if let Some(secret) = &self.secret {
+            request = request.header(SIGNATURE_HEADER, sign(secret, body));
+        }
+
+        let attempted_at = Utc::now();
+        let (status, error) = match request.send().await {
+            Ok(resp) if resp.status().is_success() => (Some(resp.status().as_u16()), None),
+            Ok(resp) => (
+                Some(resp.status().as_u16()),
+                Some(format!("HTTP {}", resp.status().as_u16())),
+            ),
+            Err(e) if e.is_timeout() => (None, Some("timed out".to_string())),
+            Err(e) => (None, Some(e.to_string())),
+        };
+
+        DeliveryAttempt {
+            attempt,
+            attempted_at,
+            status,
+            delivered: error.is_none(),
+            error,
+        }
+    }
+}
+
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac takes keys of any size");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+// callbacks are signed, so they are only accepted once a secret is configured
+pub async fn check_callback_url(config: &WebhookConfig, url: &str) -> Result<(), String> {
+    if config.secret.is_none() {
+        return Err("Callbacks need webhooks.secret to be configured".to_string());
+    }
+    Destinations::new(config.allowed_hosts.clone())
+        .check(url)
+        .await
+        .map_err(|e| format!("Invalid callback url: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::destination::Lookup;
+    use std::net::IpAddr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_sign() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_check_callback_url() {
+        let mut config = WebhookConfig {
+            secret: Some("s3cret".to_string()),
+            ..WebhookConfig::default()
+        };
+        assert!(
+            check_callback_url(&config, "https://93.184.215.14/hooks/jobs")
+                .await
+                .is_ok()
+        );
+        for url in [
+            "http://127.0.0.1:9000/",
+            "http://localhost/",
+            "http://10.1.2.3/",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://[::1]/",
+            "http://[::ffff:192.168.0.1]/",
+            "ftp://93.184.215.14/",
+            "/relative",
+        ] {
+            assert!(check_callback_url(&config, url).await.is_err(), "{}", url);
+        }
+
+        config.allowed_hosts = vec!["127.0.0.1".to_string()];
+        assert!(
+            check_callback_url(&config, "http://127.0.0.1:9000/")
+                .await
+                .is_ok()
+        );
+
+        config.secret = None;
+        assert!(
+            check_callback_url(&config, "https://93.184.215.14/")
+                .await
+                .is_err()
+        );
+    }
+
+    // resolves to a public address once, then to loopback
+    struct Rebinding(AtomicUsize);
+
+    #[async_trait::async_trait]
+    impl Lookup for Rebinding {
+        async fn lookup(&self, _host: &str) -> std::io::Result<Vec<IpAddr>> {
+            let ip = match self.0.fetch_add(1, Ordering::SeqCst) {
+                0 => "93.184.215.14",
+                _ => "127.0.0.1",
+            };
+            Ok(vec![ip.parse().unwrap()])
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_rebound_host_not_called() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.set_nonblocking(true).unwrap();
+        let url = format!(
+            "http://rebind.example:{}/",
+            listener.local_addr().unwrap().port()
+        );
+
+        let lookup = Arc::new(Rebinding(AtomicUsize::new(0)));
+        let destinations = Destinations::new(Vec::new()).with_lookup(lookup.clone());
+        let sender =
+            WebhookSender::with_destinations(&WebhookConfig::default(), destinations).unwrap();
+
+        let attempt = sender.send(&url, "delivery", 1, b"{}").await;
+        assert!(!attempt.delivered);
+        assert_eq!(attempt.status, None);
+        // checked once, then resolved again for the connection and refused
+        assert_eq!(lookup.0.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            listener.accept().unwrap_err().kind(),
+            std::io::ErrorKind::WouldBlock
+        );
+    }
+}
@@ -1,7 +1,9 @@
 pub mod api;
 pub mod domain;
 pub mod error;
+pub mod i18n;
 pub mod infrastructure;
 pub mod pipeline;
+pub mod rendering;
 
 pub use error::AppError;
@@ -1,9 +1,11 @@
 use actix_web::{App, HttpServer, middleware, web};
 use email_processor::{
-    api::{AppState, routes},
+    api::{AppState, request_id::RequestId, routes},
     infrastructure::{logging, metrics, Config},
+    pipeline::revalidation::parse_schedule,
 };
 use std::net::SocketAddr;
+use std::time::Duration;
 use tracing::info;
 
 #[actix_web::main]
@@ -34,6 +36,24 @@ async fn main() -> anyhow::Result<()> {
     // create shared application state
     let app_state = AppState::new(config.clone());
 
+    // pick up edits to the organization directory without a restart
+    if let Some(directory) = &app_state.directory {
+        directory
+            .clone()
+            .watch(Duration::from_secs(config.directory.reload_interval_secs));
+    }
+
+    // continue batch jobs the last run didn't finish
+    app_state.jobs.resume();
+
+    // re-check stored signatures against the current rules
+    if let Some(schedule) = &config.revalidation.schedule {
+        app_state
+            .revalidator
+            .clone()
+            .schedule(parse_schedule(schedule)?);
+    }
+
     // build server
     let bind_addr = format!("{}:{}", config.server.host, config.server.port);
     info!(bind_addr = %bind_addr, "Binding server");
@@ -43,7 +63,8 @@ async fn main() -> anyhow::Result<()> {
             .app_data(web::Data::new(app_state.clone()))
             // middleware
             .wrap(middleware::Logger::default()) // HTTP request logging
-            .wrap(middleware::Compress::default()) // Response compression
+            .wrap(RequestId) // X-Request-Id, around everything but compression so every response carries it
+            .wrap(middleware::Compress::default()) // Response compression, of the final body
             // routes
             .configure(routes::configure)
     })
@@ -0,0 +1,163 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::error::{AppError, Overload, OverloadReason};
+use crate::infrastructure::metrics;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AdmissionLimits {
+    // signatures being processed at once, across all requests
+    pub max_in_flight_signatures: usize,
+    // batches accepted but not finished yet
+    pub max_queued_batches: usize,
+    // suggested to rejected clients
+    pub retry_after_secs: u64,
+}
+
+impl Default for AdmissionLimits {
+    fn default() -> Self {
+        Self {
+            max_in_flight_signatures: 10_000,
+            max_queued_batches: 8,
+            retry_after_secs: 1,
+        }
+    }
+}
+
+// turns work away once the pipeline is full instead of letting latency pile up
+pub struct Admission {
+    limits: AdmissionLimits,
+    in_flight: AtomicUsize,
+    batches: AtomicUsize,
+}
+
+impl Admission {
+    pub fn new(limits: AdmissionLimits) -> Self {
+        Self {
+            limits,
+            in_flight: AtomicUsize::new(0),
+            batches: AtomicUsize::new(0),
+        }
+    }
+
+    // reserve room for a request's signatures, released when the permit is dropped
+    pub fn admit(self: &Arc<Self>, signatures: usize, batch: bool) -> Result<Permit, AppError> {
+        if batch && !reserve(&self.batches, 1, self.limits.max_queued_batches) {
+            return Err(self.overload(OverloadReason::TooManyBatches));
+        }
+
+        if !reserve(
+            &self.in_flight,
+            signatures,
+            self.limits.max_in_flight_signatures,
+        ) {
+            if batch {
+                self.batches.fetch_sub(1, Ordering::AcqRel);
+            }
+            return Err(self.overload(OverloadReason::Saturated));
+        }
+
+        self.report();
+        Ok(Permit {
+            admission: self.clone(),
+            signatures,
+            batch,
+        })
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::Acquire)
+    }
+
+    pub fn queued_batches(&self) -> usize {
+        self.batches.load(Ordering::Acquire)
+    }
+
+    fn overload(&self, reason: OverloadReason) -> AppError {
+        metrics::record_rejection(reason.as_str());
+        AppError::Overloaded(Overload {
+            reason,
+            retry_after: Duration::from_secs(self.limits.retry_after_secs),
+        })
+    }
+
+    fn report(&self) {
+        metrics::set_pipeline_depth(self.in_flight(), self.queued_batches());
+    }
+}
+
+// add to a counter unless that would take it past the limit
+fn reserve(counter: &AtomicUsize, amount: usize, limit: usize) -> bool {
+    counter
+        .fetch_update(Ordering::AcqRel, Ordering::Acquire, |current| {
+            (current + amount <= limit).then_some(current + amount)
+        })
+        .is_ok()
+}
+
+pub struct Permit {
+    admission: Arc<Admission>,
+    signatures: usize,
+    batch: bool,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.admission
+            .in_flight
+            .fetch_sub(self.signatures, Ordering::AcqRel);
+        if self.batch {
+            self.admission.batches.fetch_sub(1, Ordering::AcqRel);
+        }
+        self.admission.report();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn admission(max_in_flight_signatures: usize, max_queued_batches: usize) -> Arc<Admission> {
+        Arc::new(Admission::new(AdmissionLimits {
+            max_in_flight_signatures,
+            max_queued_batches,
+            retry_after_secs: 2,
+        }))
+    }
+
+    #[test]
+    fn test_batches_limited() {
+        let admission = admission(100, 1);
+        let permit = admission.admit(10, true).unwrap();
+
+        let Err(AppError::Overloaded(overload)) = admission.admit(10, true) else {
+            panic!("second batch admitted");
+        };
+        assert_eq!(overload.reason, OverloadReason::TooManyBatches);
+        assert_eq!(overload.retry_after, Duration::from_secs(2));
+
+        // single signatures still get through
+        assert!(admission.admit(1, false).is_ok());
+
+        drop(permit);
+        assert!(admission.admit(10, true).is_ok());
+    }
+
+    #[test]
+    fn test_signatures_limited() {
+        let admission = admission(10, 4);
+        let _permit = admission.admit(8, true).unwrap();
+
+        let Err(AppError::Overloaded(overload)) = admission.admit(3, true) else {
+            panic!("over the signature limit");
+        };
+        assert_eq!(overload.reason, OverloadReason::Saturated);
+        // the rejected batch gave its slot back
+        assert_eq!(admission.queued_batches(), 1);
+        assert_eq!(admission.in_flight(), 8);
+    }
+}
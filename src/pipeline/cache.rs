@@ -0,0 +1,171 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use sha2::{Digest, Sha256};
+
+use crate::domain::{EmailSignature, ValidationResult};
+
+// results of validating a signature, keyed by its content and the rules it was checked against
+pub trait ValidationCache: Send + Sync {
+    fn get(&self, key: &str) -> Option<ValidationResult>;
+    fn put(&self, key: String, result: ValidationResult);
+}
+
+// hash of what a signature says, ignoring its identity, plus the rules version
+pub fn cache_key(sig: &EmailSignature, rules_version: &str) -> String {
+    let mut content = serde_json::to_value(sig).unwrap_or_default();
+    if let Some(fields) = content.as_object_mut() {
+        fields.remove("id");
+        fields.remove("created_at");
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(rules_version.as_bytes());
+    hasher.update([0]);
+    hasher.update(content.to_string().as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+struct Entry {
+    result: ValidationResult,
+    inserted_at: Instant,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct LruState {
+    entries: HashMap<String, Entry>,
+    // last use tick -> key, oldest first
+    recency: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl LruState {
+    fn touch(&mut self, key: &str) {
+        self.tick += 1;
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.recency.remove(&entry.last_used);
+            entry.last_used = self.tick;
+            self.recency.insert(self.tick, key.to_string());
+        }
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.recency.remove(&entry.last_used);
+        }
+    }
+}
+
+// in-memory cache evicting the least recently used entry once full
+pub struct LruValidationCache {
+    max_entries: usize,
+    ttl: Duration,
+    state: Mutex<LruState>,
+}
+
+impl LruValidationCache {
+    pub fn new(max_entries: usize, ttl: Duration) -> Self {
+        Self {
+            max_entries,
+            ttl,
+            state: Mutex::new(LruState::default()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl ValidationCache for LruValidationCache {
+    fn get(&self, key: &str) -> Option<ValidationResult> {
+        let mut state = self.state.lock().unwrap();
+
+        let expired = state.entries.get(key)?.inserted_at.elapsed() >= self.ttl;
+        if expired {
+            state.remove(key);
+            return None;
+        }
+
+        state.touch(key);
+        state.entries.get(key).map(|entry| entry.result.clone())
+    }
+
+    fn put(&self, key: String, result: ValidationResult) {
+        if self.max_entries == 0 {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.remove(&key);
+
+        while state.entries.len() >= self.max_entries {
+            let Some((_, oldest)) = state.recency.pop_first() else {
+                break;
+            };
+            state.entries.remove(&oldest);
+        }
+
+        state.tick += 1;
+        let tick = state.tick;
+        state.recency.insert(tick, key.clone());
+        state.entries.insert(
+            key,
+            Entry {
+                result,
+                inserted_at: Instant::now(),
+                last_used: tick,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::SignatureValidator;
+
+    fn result() -> ValidationResult {
+        SignatureValidator::new().validate(&EmailSignature::builder().build())
+    }
+
+    #[test]
+    fn test_key_ignores_identity() {
+        let a = EmailSignature::builder().name("Jane Roe").build();
+        let mut b = a.clone();
+        b.id = uuid::Uuid::new_v4();
+
+        assert_eq!(cache_key(&a, "1"), cache_key(&b, "1"));
+        assert_ne!(cache_key(&a, "1"), cache_key(&a, "2"));
+        b.name = "Jane Doe".to_string();
+        assert_ne!(cache_key(&a, "1"), cache_key(&b, "1"));
+    }
+
+    #[test]
+    fn test_least_recently_used_evicted() {
+        let cache = LruValidationCache::new(2, Duration::from_secs(60));
+        cache.put("a".to_string(), result());
+        cache.put("b".to_string(), result());
+        assert!(cache.get("a").is_some());
+
+        cache.put("c".to_string(), result());
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("c").is_some());
+    }
+
+    #[test]
+    fn test_expired_entries_miss() {
+        let cache = LruValidationCache::new(2, Duration::ZERO);
+        cache.put("a".to_string(), result());
+        assert!(cache.get("a").is_none());
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,1184 @@
+use std::collections::HashMap;
+use std::panic::AssertUnwindSafe;
+use std::path::Path;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use futures_util::FutureExt;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::domain::{BatchSummary, EmailSignature, ValidationError, ValidationResult};
+use crate::infrastructure::metrics;
+use crate::infrastructure::webhook::{DeliveryAttempt, WebhookSender};
+use crate::pipeline::admission::Permit;
+use crate::pipeline::journal::{JobEvent, JobJournal, RecoveredJob};
+use crate::pipeline::manager::PipelineManager;
+use crate::pipeline::stage::Cancellation;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct JobSettings {
+    // signatures run through the pipeline at a time, a cancelled job keeps every finished chunk
+    pub chunk_size: usize,
+    // finished jobs kept around for clients to fetch, the oldest are dropped first
+    pub retain_finished: usize,
+    // append-only file jobs are recorded in and resumed from after a restart, in memory if unset
+    pub journal: Option<String>,
+    // tries per signature before it goes to the dead letters
+    pub max_attempts: u32,
+    // wait before the first retry, doubled for each one after
+    pub retry_backoff_ms: u64,
+}
+
+impl Default for JobSettings {
+    fn default() -> Self {
+        Self {
+            chunk_size: 100,
+            retain_finished: 1000,
+            journal: None,
+            max_attempts: 3,
+            retry_backoff_ms: 500,
+        }
+    }
+}
+
+impl JobSettings {
+    fn backoff(&self, attempt: u32) -> Duration {
+        Duration::from_millis(
+            self.retry_backoff_ms
+                .saturating_mul(1 << (attempt - 1).min(16)),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    // asked to stop, the chunk in flight is still finishing
+    Cancelling,
+    Completed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Running => "running",
+            Self::Cancelling => "cancelling",
+            Self::Completed => "completed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Completed | Self::Cancelled)
+    }
+}
+
+// a batch validated in the background, with the results so far
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub id: Uuid,
+    pub status: JobStatus,
+    pub total: usize,
+    // signatures done with, including the dead letters
+    pub processed: usize,
+    pub results: Vec<ValidationResult>,
+    // signatures given up on after failing every attempt
+    pub dead_letters: Vec<DeadLetter>,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    // notified once the job is finished
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub callback_url: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub deliveries: Vec<DeliveryAttempt>,
+}
+
+impl Job {
+    pub fn new(id: Uuid, total: usize, created_at: DateTime<Utc>) -> Self {
+        Self {
+            id,
+            status: JobStatus::Queued,
+            total,
+            processed: 0,
+            results: Vec::new(),
+            dead_letters: Vec::new(),
+            created_at,
+            finished_at: None,
+            callback_url: None,
+            deliveries: Vec::new(),
+        }
+    }
+
+    pub fn summary(&self) -> BatchSummary {
+        let elapsed = self.finished_at.unwrap_or_else(Utc::now) - self.created_at;
+        BatchSummary::new(
+            self.total,
+            &self.results,
+            elapsed.num_milliseconds().max(0) as u128,
+        )
+    }
+
+    // where the job stands, with the errors of the signatures just finished
+    pub fn progress(&self, latest_errors: Vec<SignatureErrors>) -> JobProgress {
+        JobProgress {
+            job_id: self.id,
+            processed: self.processed,
+            total: self.total,
+            summary: self.summary(),
+            dead_letters: self.dead_letters.len(),
+            latest_errors,
+        }
+    }
+
+    fn delivered(&self) -> bool {
+        self.deliveries.iter().any(|attempt| attempt.delivered)
+    }
+}
+
+// a signature that kept failing, with the last reason why
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub job_id: Uuid,
+    // position in the submitted batch
+    pub index: usize,
+    pub signature_id: Uuid,
+    pub attempts: u32,
+    pub error: String,
+    pub failed_at: DateTime<Utc>,
+}
+
+// sent to subscribers after each chunk
+#[derive(Debug, Clone, Serialize)]
+pub struct JobProgress {
+    pub job_id: Uuid,
+    pub processed: usize,
+    pub total: usize,
+    // counts over the results so far
+    pub summary: BatchSummary,
+    pub dead_letters: usize,
+    pub latest_errors: Vec<SignatureErrors>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SignatureErrors {
+    pub signature_id: Uuid,
+    pub errors: Vec<ValidationError>,
+}
+
+// what subscribers to a job hear about, ending with `Finished`
+#[derive(Debug, Clone)]
+pub enum JobUpdate {
+    Progress(JobProgress),
+    Finished(JobNotification),
+}
+
+// progress events carry at most this many invalid signatures of a chunk
+const LATEST_ERRORS: usize = 10;
+
+fn latest_errors(results: &[ValidationResult]) -> Vec<SignatureErrors> {
+    results
+        .iter()
+        .filter(|result| !result.valid)
+        .take(LATEST_ERRORS)
+        .map(|result| SignatureErrors {
+            signature_id: result.signature_id,
+            errors: result.errors.clone(),
+        })
+        .collect()
+}
+
+// posted to a job's callback url once it is finished, and the last event for subscribers
+#[derive(Debug, Clone, Serialize)]
+pub struct JobNotification {
+    pub job_id: Uuid,
+    pub status: JobStatus,
+    pub summary: BatchSummary,
+    pub dead_letters: usize,
+    // where the full results can be fetched
+    pub results_url: String,
+}
+
+struct Tracked {
+    job: Job,
+    cancellation: Cancellation,
+    updates: broadcast::Sender<JobUpdate>,
+}
+
+impl Tracked {
+    fn new(job: Job, cancellation: Cancellation) -> Self {
+        Self {
+            job,
+            cancellation,
+            updates: broadcast::channel(UPDATES_BUFFERED).0,
+        }
+    }
+}
+
+// a subscriber further behind than this skips ahead, progress counts are cumulative anyway
+const UPDATES_BUFFERED: usize = 64;
+
+// batch jobs, run chunk by chunk through the pipeline until done or cancelled
+pub struct JobQueue {
+    pipeline: Arc<PipelineManager>,
+    settings: JobSettings,
+    jobs: RwLock<HashMap<Uuid, Tracked>>,
+    journal: Option<JobJournal>,
+    // unfinished jobs found in the journal, waiting for `resume`
+    recovered: Mutex<Vec<RecoveredJob>>,
+    webhooks: Option<Arc<WebhookSender>>,
+    // prefix of the result links in callbacks
+    results_base_url: String,
+}
+
+impl JobQueue {
+    pub fn new(pipeline: Arc<PipelineManager>, settings: JobSettings) -> Self {
+        Self {
+            pipeline,
+            settings,
+            jobs: RwLock::new(HashMap::new()),
+            journal: None,
+            recovered: Mutex::new(Vec::new()),
+            webhooks: None,
+            results_base_url: String::new(),
+        }
+    }
+
+    // call back jobs submitted with a callback url once they are finished
+    pub fn with_webhooks(
+        mut self,
+        sender: WebhookSender,
+        results_base_url: impl Into<String>,
+    ) -> Self {
+        self.webhooks = Some(Arc::new(sender));
+        self.results_base_url = results_base_url.into();
+        self
+    }
+
+    // jobs recorded in a journal, unfinished ones continue once `resume` is called
+    pub fn open(
+        pipeline: Arc<PipelineManager>,
+        settings: JobSettings,
+        path: impl AsRef<Path>,
+    ) -> anyhow::Result<Self> {
+        let (journal, recovered) = JobJournal::open(path, settings.retain_finished)?;
+        let mut queue = Self::new(pipeline, settings);
+        queue.journal = Some(journal);
+
+        let mut unfinished = Vec::new();
+        {
+            let mut jobs = queue.jobs.write().unwrap();
+            for recovered in recovered {
+                let cancellation = Cancellation::new();
+                if recovered.job.status == JobStatus::Cancelling {
+                    cancellation.cancel();
+                }
+                jobs.insert(
+                    recovered.job.id,
+                    Tracked::new(recovered.job.clone(), cancellation),
+                );
+                if !recovered.job.status.is_finished() {
+                    unfinished.push(recovered);
+                }
+            }
+        }
+        queue.recovered = Mutex::new(unfinished);
+
+        Ok(queue)
+    }
+
+    // pick up where unfinished jobs from the journal left off, the chunk in flight runs again,
+    // and retry callbacks for finished ones that weren't delivered, jobs that were cancelling
+    // are finished as cancelled without running anything
+    pub fn resume(self: &Arc<Self>) {
+        let recovered = std::mem::take(&mut *self.recovered.lock().unwrap());
+
+        let undelivered: Vec<Uuid> = self
+            .jobs
+            .read()
+            .unwrap()
+            .values()
+            .filter(|tracked| {
+                let job = &tracked.job;
+                job.status.is_finished() && job.callback_url.is_some() && !job.delivered()
+            })
+            .map(|tracked| tracked.job.id)
+            .collect();
+        for id in undelivered {
+            self.notify(id);
+        }
+
+        for RecoveredJob { job, signatures } in recovered {
+            let Some(cancellation) = self
+                .jobs
+                .read()
+                .unwrap()
+                .get(&job.id)
+                .map(|tracked| tracked.cancellation.clone())
+            else {
+                continue;
+            };
+
+            info!(job_id = %job.id, processed = job.processed, total = job.total, "Resuming batch job");
+            // admitted before the restart, not held against the limits again
+            tokio::spawn(self.clone().process(
+                job.id,
+                signatures,
+                job.processed,
+                cancellation,
+                None,
+            ));
+        }
+    }
+
+    // start working on a batch in the background, the permit is held until the job finishes
+    pub async fn submit(
+        self: &Arc<Self>,
+        sigs: Vec<EmailSignature>,
+        callback_url: Option<String>,
+        permit: Permit,
+    ) -> Job {
+        let mut job = Job::new(Uuid::new_v4(), sigs.len(), Utc::now());
+        job.callback_url = callback_url;
+        let cancellation = Cancellation::new();
+
+        self.record(JobEvent::Submitted {
+            id: job.id,
+            created_at: job.created_at,
+            total: job.total,
+            signatures: sigs.clone(),
+            callback_url: job.callback_url.clone(),
+        })
+        .await;
+        {
+            let mut jobs = self.jobs.write().unwrap();
+            jobs.insert(job.id, Tracked::new(job.clone(), cancellation.clone()));
+            self.prune(&mut jobs);
+        }
+
+        info!(job_id = %job.id, total = job.total, "Batch job queued");
+        tokio::spawn(
+            self.clone()
+                .process(job.id, sigs, 0, cancellation, Some(permit)),
+        );
+        job
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<Job> {
+        self.jobs
+            .read()
+            .unwrap()
+            .get(&id)
+            .map(|tracked| tracked.job.clone())
+    }
+
+    // the job as it is now and its updates from here on
+    pub fn subscribe(&self, id: Uuid) -> Option<(Job, broadcast::Receiver<JobUpdate>)> {
+        let jobs = self.jobs.read().unwrap();
+        let tracked = jobs.get(&id)?;
+        Some((tracked.job.clone(), tracked.updates.subscribe()))
+    }
+
+    pub fn notification(&self, job: &Job) -> JobNotification {
+        JobNotification {
+            job_id: job.id,
+            status: job.status,
+            summary: job.summary(),
+            dead_letters: job.dead_letters.len(),
+            results_url: format!("{}/api/v1/jobs/{}", self.results_base_url, job.id),
+        }
+    }
+
+    // every job's dead letters, oldest job first
+    pub fn dead_letters(&self) -> Vec<DeadLetter> {
+        let mut letters: Vec<DeadLetter> = self
+            .jobs
+            .read()
+            .unwrap()
+            .values()
+            .flat_map(|tracked| tracked.job.dead_letters.clone())
+            .collect();
+        letters.sort_by_key(|letter| letter.failed_at);
+        letters
+    }
+
+    // stop a job after the chunk it is working on, it stays cancelling until that chunk is done,
+    // finished jobs are left as they are
+    pub async fn cancel(&self, id: Uuid) -> Option<Job> {
+        let status = self.get(id)?.status;
+        // on disk first, so a restart doesn't run the job to completion
+        if matches!(status, JobStatus::Queued | JobStatus::Running) {
+            self.record(JobEvent::Cancelling { id }).await;
+        }
+
+        let mut jobs = self.jobs.write().unwrap();
+        let tracked = jobs.get_mut(&id)?;
+        if matches!(tracked.job.status, JobStatus::Queued | JobStatus::Running) {
+            tracked.cancellation.cancel();
+            tracked.job.status = JobStatus::Cancelling;
+            info!(job_id = %id, processed = tracked.job.processed, "Cancelling batch job");
+        }
+        Some(tracked.job.clone())
+    }
+
+    async fn process(
+        self: Arc<Self>,
+        id: Uuid,
+        sigs: Vec<EmailSignature>,
+        mut processed: usize,
+        cancellation: Cancellation,
+        _permit: Option<Permit>,
+    ) {
+        self.update(id, |job| {
+            if job.status == JobStatus::Queued {
+                job.status = JobStatus::Running;
+            }
+        });
+
+        // chunks are validated one at a time, duplicates are looked for across the whole job
+        let duplicates = Arc::new(self.pipeline.find_duplicates(&sigs).await);
+        while processed < sigs.len() && !cancellation.is_cancelled() {
+            let end = sigs.len().min(processed + self.settings.chunk_size);
+            let (results, dead_letters) = self
+                .run_chunk(
+                    id,
+                    processed,
+                    &sigs[processed..end],
+                    &duplicates,
+                    &cancellation,
+                )
+                .await;
+            processed = end;
+
+            // written down before it shows, a crash before this runs the chunk again
+            self.record(JobEvent::Chunk {
+                id,
+                processed,
+                results: results.clone(),
+                dead_letters: dead_letters.clone(),
+            })
+            .await;
+            let latest = latest_errors(&results);
+            let progress = self.update(id, |job| {
+                job.processed = processed;
+                job.results.extend(results);
+                job.dead_letters.extend(dead_letters);
+                job.progress(latest)
+            });
+            if let Some(progress) = progress {
+                self.publish(id, JobUpdate::Progress(progress));
+            }
+        }
+
+        // nothing runs for the job past this point, so a cancelled one is only now finished
+        let finished = self.update(id, |job| {
+            job.status = match job.status {
+                JobStatus::Cancelling => JobStatus::Cancelled,
+                JobStatus::Running | JobStatus::Queued => JobStatus::Completed,
+                JobStatus::Completed | JobStatus::Cancelled => return None,
+            };
+            job.finished_at = Some(Utc::now());
+            info!(job_id = %job.id, status = job.status.as_str(), processed = job.processed, total = job.total, dead_letters = job.dead_letters.len(), "Batch job finished");
+            metrics::record_job_finished(job.status.as_str());
+            Some(job.clone())
+        });
+        if let Some(job) = finished.flatten() {
+            self.record(JobEvent::Finished {
+                id,
+                status: job.status,
+                finished_at: job.finished_at.unwrap_or_else(Utc::now),
+            })
+            .await;
+            self.finish(&job);
+        }
+    }
+
+    // tell subscribers and the callback url that the job is done
+    fn finish(self: &Arc<Self>, job: &Job) {
+        self.publish(job.id, JobUpdate::Finished(self.notification(job)));
+        self.notify(job.id);
+    }
+
+    fn publish(&self, id: Uuid, update: JobUpdate) {
+        if let Some(tracked) = self.jobs.read().unwrap().get(&id) {
+            // nobody listening is fine
+            let _ = tracked.updates.send(update);
+        }
+    }
+
+    fn notify(self: &Arc<Self>, id: Uuid) {
+        let wanted = self.get(id).is_some_and(|job| job.callback_url.is_some());
+        if wanted && self.webhooks.is_some() {
+            tokio::spawn(self.clone().deliver(id));
+        }
+    }
+
+    // post the outcome to the job's callback url, retrying with backoff until it's accepted
+    async fn deliver(self: Arc<Self>, id: Uuid) {
+        let (Some(sender), Some(job)) = (self.webhooks.clone(), self.get(id)) else {
+            return;
+        };
+        let Some(url) = job.callback_url.clone() else {
+            return;
+        };
+
+        let body = serde_json::to_vec(&self.notification(&job)).expect("notification serializes");
+        let delivery = id.to_string();
+
+        let mut attempt = job.deliveries.len() as u32;
+        while attempt < sender.max_attempts() {
+            if attempt > 0 {
+                tokio::time::sleep(sender.backoff(attempt)).await;
+            }
+            attempt += 1;
+
+            let result = sender.send(&url, &delivery, attempt, &body).await;
+            let delivered = result.delivered;
+            self.record(JobEvent::Delivery {
+                id,
+                attempt: result.clone(),
+            })
+            .await;
+            self.update(id, |job| job.deliveries.push(result));
+            metrics::record_webhook_delivery(delivered);
+
+            if delivered {
+                info!(job_id = %id, attempt, "Job callback delivered");
+                return;
+            }
+        }
+
+        warn!(job_id = %id, url = %url, attempts = attempt, "Giving up on job callback");
+    }
+
+    // run a chunk, retrying signatures that timed out or hit a crashing stage with backoff
+    async fn run_chunk(
+        &self,
+        id: Uuid,
+        offset: usize,
+        chunk: &[EmailSignature],
+        duplicates: &Arc<Vec<Vec<ValidationError>>>,
+        cancellation: &Cancellation,
+    ) -> (Vec<ValidationResult>, Vec<DeadLetter>) {
+        let mut pending: Vec<usize> = (0..chunk.len()).collect();
+        let mut results = Vec::with_capacity(chunk.len());
+        let mut dead_letters = Vec::new();
+
+        for attempt in 1..=self.settings.max_attempts.max(1) {
+            let sigs = pending
+                .iter()
+                .map(|&i| (offset + i, chunk[i].clone()))
+                .collect();
+            let run =
+                self.pipeline
+                    .validate_part_cancellable(sigs, duplicates.clone(), cancellation);
+
+            let failed: Vec<(usize, String)> = match AssertUnwindSafe(run).catch_unwind().await {
+                Ok(items) => items
+                    .into_iter()
+                    .filter(|item| !item.is_cancelled())
+                    .filter_map(|item| {
+                        if item.timed_out() {
+                            let error = item.result.errors.last().map(|e| e.message.clone());
+                            return Some((item.index - offset, error.unwrap_or_default()));
+                        }
+                        results.push((item.index - offset, item.result));
+                        None
+                    })
+                    .collect(),
+                Err(_) => pending
+                    .iter()
+                    .map(|&i| {
+                        (
+                            i,
+                            "Pipeline failed while processing the signature".to_string(),
+                        )
+                    })
+                    .collect(),
+            };
+
+            if failed.is_empty() || cancellation.is_cancelled() {
+                break;
+            }
+
+            if attempt >= self.settings.max_attempts {
+                warn!(job_id = %id, count = failed.len(), attempts = attempt, "Moving failed signatures to dead letters");
+                metrics::record_dead_letters(failed.len());
+                dead_letters.extend(failed.into_iter().map(|(i, error)| DeadLetter {
+                    job_id: id,
+                    index: offset + i,
+                    signature_id: chunk[i].id,
+                    attempts: attempt,
+                    error,
+                    failed_at: Utc::now(),
+                }));
+                break;
+            }
+
+            let backoff = self.settings.backoff(attempt);
+            warn!(job_id = %id, count = failed.len(), attempt, backoff_ms = backoff.as_millis() as u64, "Retrying failed signatures");
+            tokio::time::sleep(backoff).await;
+            pending = failed.into_iter().map(|(i, _)| i).collect();
+        }
+
+        // retried signatures finish after the rest, the results keep the batch order
+        results.sort_by_key(|(index, _)| *index);
+        (
+            results.into_iter().map(|(_, result)| result).collect(),
+            dead_letters,
+        )
+    }
+
+    // waits until the event is on disk, never call it holding the jobs lock
+    async fn record(&self, event: JobEvent) {
+        if let Some(journal) = &self.journal
+            && let Err(e) = journal.append(&event).await
+        {
+            warn!(error = %e, "Failed to record job event");
+        }
+    }
+
+    fn update<R>(&self, id: Uuid, f: impl FnOnce(&mut Job) -> R) -> Option<R> {
+        self.jobs
+            .write()
+            .unwrap()
+            .get_mut(&id)
+            .map(|tracked| f(&mut tracked.job))
+    }
+
+    fn prune(&self, jobs: &mut HashMap<Uuid, Tracked>) {
+        let mut finished: Vec<(DateTime<Utc>, Uuid)> = jobs
+            .values()
+            .filter_map(|tracked| Some((tracked.job.finished_at?, tracked.job.id)))
+            .collect();
+        if finished.len() <= self.settings.retain_finished {
+            return;
+        }
+
+        finished.sort();
+        let excess = finished.len() - self.settings.retain_finished;
+        for (_, id) in finished.into_iter().take(excess) {
+            jobs.remove(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::stage::{Deadlines, PipelineItem, RunContext, Stage};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    // holds up each chunk so the job can be cancelled in between
+    struct Slow;
+
+    #[async_trait::async_trait]
+    impl Stage for Slow {
+        fn name(&self) -> &'static str {
+            "slow"
+        }
+
+        async fn process(&self, _items: &mut [&mut PipelineItem], _ctx: &RunContext) {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+
+    // does nothing, but never gets to run once the batch is out of time
+    struct Noop;
+
+    #[async_trait::async_trait]
+    impl Stage for Noop {
+        fn name(&self) -> &'static str {
+            "noop"
+        }
+
+        async fn process(&self, _items: &mut [&mut PipelineItem], _ctx: &RunContext) {}
+    }
+
+    // crashes the first time it runs
+    struct Flaky(AtomicBool);
+
+    #[async_trait::async_trait]
+    impl Stage for Flaky {
+        fn name(&self) -> &'static str {
+            "flaky"
+        }
+
+        async fn process(&self, _items: &mut [&mut PipelineItem], _ctx: &RunContext) {
+            if !self.0.swap(true, Ordering::SeqCst) {
+                panic!("flaky stage");
+            }
+        }
+    }
+
+    fn settings(chunk_size: usize) -> JobSettings {
+        JobSettings {
+            chunk_size,
+            retain_finished: 10,
+            max_attempts: 2,
+            retry_backoff_ms: 1,
+            ..JobSettings::default()
+        }
+    }
+
+    fn queue(pipeline: PipelineManager, chunk_size: usize) -> Arc<JobQueue> {
+        Arc::new(JobQueue::new(Arc::new(pipeline), settings(chunk_size)))
+    }
+
+    fn signatures(n: usize) -> Vec<EmailSignature> {
+        (0..n)
+            .map(|i| {
+                EmailSignature::builder()
+                    .email(format!("person{}@example.com", i))
+                    .build()
+            })
+            .collect()
+    }
+
+    async fn wait_until_finished(queue: &JobQueue, id: Uuid) -> Job {
+        for _ in 0..100 {
+            let job = queue.get(id).unwrap();
+            if job.status.is_finished() {
+                return job;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("job did not finish");
+    }
+
+    #[actix_web::test]
+    async fn test_job_completes() {
+        let pipeline = PipelineManager::new();
+        let permit = pipeline.admit(5, true).unwrap();
+        let queue = queue(pipeline, 2);
+
+        let job = queue.submit(signatures(5), None, permit).await;
+        assert_eq!(job.status, JobStatus::Queued);
+
+        let job = wait_until_finished(&queue, job.id).await;
+        assert_eq!(job.status, JobStatus::Completed);
+        assert_eq!(job.results.len(), 5);
+        assert!(job.finished_at.is_some());
+    }
+
+    #[actix_web::test]
+    async fn test_duplicates_found_across_chunks() {
+        let pipeline = PipelineManager::new();
+        let permit = pipeline.admit(4, true).unwrap();
+        let queue = queue(pipeline, 2);
+        let mut sigs = signatures(4);
+        sigs[3].email = sigs[0].email.to_uppercase();
+
+        let job = queue.submit(sigs, None, permit).await;
+        let job = wait_until_finished(&queue, job.id).await;
+
+        let valid: Vec<bool> = job.results.iter().map(|r| r.valid).collect();
+        assert_eq!(valid, vec![false, true, true, false]);
+        assert_eq!(job.results[0].errors[0].params["indexes"], "3");
+        assert_eq!(job.results[3].errors[0].params["indexes"], "0");
+    }
+
+    #[actix_web::test]
+    async fn test_cancel_keeps_finished_chunks() {
+        let pipeline = PipelineManager::builder()
+            .stage(Arc::new(Slow))
+            .build()
+            .unwrap();
+        let permit = pipeline.admit(10, true).unwrap();
+        let queue = queue(pipeline, 2);
+
+        let job = queue.submit(signatures(10), None, permit).await;
+        tokio::time::sleep(Duration::from_millis(75)).await;
+        let cancelling = queue.cancel(job.id).await.unwrap();
+        assert_eq!(cancelling.status, JobStatus::Cancelling);
+        assert!(cancelling.finished_at.is_none());
+        let (_, mut updates) = queue.subscribe(job.id).unwrap();
+
+        // the chunk in progress may still land, nothing after it
+        let job = wait_until_finished(&queue, job.id).await;
+        assert_eq!(job.status, JobStatus::Cancelled);
+        assert!(job.finished_at.is_some());
+        assert!((2..=4).contains(&job.results.len()));
+        let finished = loop {
+            if let JobUpdate::Finished(notification) = updates.recv().await.unwrap() {
+                break notification;
+            }
+        };
+        assert_eq!(finished.status, JobStatus::Cancelled);
+        assert_eq!(finished.summary.valid, job.results.len());
+
+        // cancelling again changes nothing
+        assert_eq!(
+            queue.cancel(job.id).await.unwrap().finished_at,
+            job.finished_at
+        );
+        assert!(queue.cancel(Uuid::new_v4()).await.is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_failing_signatures_dead_lettered() {
+        // the batch runs out of time in the slow stage on every attempt
+        let pipeline = PipelineManager::builder()
+            .deadlines(Deadlines {
+                signature_ms: 5000,
+                batch_ms: 10,
+            })
+            .stage(Arc::new(Slow))
+            .stage(Arc::new(Noop))
+            .build()
+            .unwrap();
+        let permit = pipeline.admit(3, true).unwrap();
+        let queue = queue(pipeline, 10);
+
+        let job = queue.submit(signatures(3), None, permit).await;
+        let job = wait_until_finished(&queue, job.id).await;
+
+        assert_eq!(job.status, JobStatus::Completed);
+        assert_eq!(job.processed, 3);
+        assert!(job.results.is_empty());
+        assert_eq!(job.dead_letters.len(), 3);
+        assert_eq!(job.dead_letters[0].attempts, 2);
+        assert!(job.dead_letters[0].error.contains("noop"));
+        assert_eq!(queue.dead_letters().len(), 3);
+    }
+
+    #[actix_web::test]
+    async fn test_crashed_chunk_retried() {
+        let pipeline = PipelineManager::builder()
+            .stage(Arc::new(Flaky(AtomicBool::new(false))))
+            .build()
+            .unwrap();
+        let permit = pipeline.admit(4, true).unwrap();
+        let queue = queue(pipeline, 2);
+
+        let job = queue.submit(signatures(4), None, permit).await;
+        let job = wait_until_finished(&queue, job.id).await;
+
+        assert_eq!(job.status, JobStatus::Completed);
+        assert_eq!(job.results.len(), 4);
+        assert!(job.dead_letters.is_empty());
+    }
+
+    // times out the first signature of the first run
+    struct SlowFirst(AtomicBool);
+
+    #[async_trait::async_trait]
+    impl Stage for SlowFirst {
+        fn name(&self) -> &'static str {
+            "slow-first"
+        }
+
+        async fn process(&self, items: &mut [&mut PipelineItem], _ctx: &RunContext) {
+            if !self.0.swap(true, Ordering::SeqCst) {
+                items[0].time_out(self.name());
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_retried_results_keep_order() {
+        let pipeline = PipelineManager::builder()
+            .stage(Arc::new(SlowFirst(AtomicBool::new(false))))
+            .build()
+            .unwrap();
+        let permit = pipeline.admit(3, true).unwrap();
+        let queue = queue(pipeline, 10);
+        let sigs = signatures(3);
+
+        let job = queue.submit(sigs.clone(), None, permit).await;
+        let job = wait_until_finished(&queue, job.id).await;
+
+        let ids: Vec<Uuid> = job.results.iter().map(|r| r.signature_id).collect();
+        assert_eq!(ids, sigs.iter().map(|s| s.id).collect::<Vec<_>>());
+        assert!(job.results.iter().all(|r| r.valid));
+    }
+
+    #[actix_web::test]
+    async fn test_unfinished_job_resumed_from_journal() {
+        let path = std::env::temp_dir().join(format!("jobs-{}.jsonl", Uuid::new_v4()));
+        let sigs = signatures(4);
+        let id = Uuid::new_v4();
+
+        // a previous run got through the first chunk, then died writing the second
+        let (journal, _) = JobJournal::open(&path, 10).unwrap();
+        journal
+            .append(&JobEvent::Submitted {
+                id,
+                created_at: Utc::now(),
+                total: 4,
+                signatures: sigs.clone(),
+                callback_url: None,
+            })
+            .await
+            .unwrap();
+        journal
+            .append(&JobEvent::Chunk {
+                id,
+                processed: 2,
+                results: sigs[..2]
+                    .iter()
+                    .map(|sig| PipelineItem::new(0, sig.clone()).result)
+                    .collect(),
+                dead_letters: Vec::new(),
+            })
+            .await
+            .unwrap();
+        drop(journal);
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        std::io::Write::write_all(&mut file, b"{\"event\":\"chu").unwrap();
+
+        let queue =
+            Arc::new(JobQueue::open(Arc::new(PipelineManager::new()), settings(2), &path).unwrap());
+        let job = queue.get(id).unwrap();
+        assert_eq!(job.status, JobStatus::Queued);
+        assert_eq!(job.processed, 2);
+
+        queue.resume();
+        let job = wait_until_finished(&queue, id).await;
+        assert_eq!(job.status, JobStatus::Completed);
+        assert_eq!(job.processed, 4);
+        let ids: Vec<Uuid> = job.results.iter().map(|r| r.signature_id).collect();
+        assert_eq!(ids, sigs.iter().map(|s| s.id).collect::<Vec<_>>());
+
+        // finished now, nothing left to resume and the signatures were compacted away
+        let (_, recovered) = JobJournal::open(&path, 10).unwrap();
+        assert_eq!(recovered.len(), 1);
+        assert_eq!(recovered[0].job.status, JobStatus::Completed);
+        assert_eq!(recovered[0].job.results.len(), 4);
+        assert!(recovered[0].signatures.is_empty());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_journal_compacted_while_running() {
+        let path = std::env::temp_dir().join(format!("jobs-{}.jsonl", Uuid::new_v4()));
+        let (journal, _) = JobJournal::open(&path, 2).unwrap();
+
+        let mut ids = Vec::new();
+        for _ in 0..5 {
+            let id = Uuid::new_v4();
+            ids.push(id);
+            journal
+                .append(&JobEvent::Submitted {
+                    id,
+                    created_at: Utc::now(),
+                    total: 4,
+                    signatures: signatures(4),
+                    callback_url: None,
+                })
+                .await
+                .unwrap();
+            journal
+                .append(&JobEvent::Finished {
+                    id,
+                    status: JobStatus::Completed,
+                    finished_at: Utc::now(),
+                })
+                .await
+                .unwrap();
+        }
+
+        // compacted down to the last two after the fourth, the fifth appended since
+        let written = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(written.lines().count(), 6);
+        assert!(!written.contains(&ids[1].to_string()));
+        assert!(written.contains(&ids[2].to_string()));
+        // finished jobs are compacted without their signatures
+        assert_eq!(written.matches("person0@example.com").count(), 1);
+
+        drop(journal);
+        let (_, recovered) = JobJournal::open(&path, 2).unwrap();
+        let recovered: Vec<Uuid> = recovered.iter().map(|r| r.job.id).collect();
+        assert_eq!(recovered, ids[3..]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_cancelling_job_not_resumed() {
+        let path = std::env::temp_dir().join(format!("jobs-{}.jsonl", Uuid::new_v4()));
+        let pipeline = PipelineManager::builder()
+            .stage(Arc::new(Slow))
+            .build()
+            .unwrap();
+        let permit = pipeline.admit(10, true).unwrap();
+        let queue = Arc::new(JobQueue::open(Arc::new(pipeline), settings(2), &path).unwrap());
+
+        // the process stops while the job is still cancelling
+        let job = queue.submit(signatures(10), None, permit).await;
+        tokio::time::sleep(Duration::from_millis(75)).await;
+        let cancelling = queue.cancel(job.id).await.unwrap();
+        assert_eq!(cancelling.status, JobStatus::Cancelling);
+        let (_, recovered) = JobJournal::open(&path, 10).unwrap();
+        let processed = recovered[0].job.processed;
+        assert_eq!(recovered[0].job.status, JobStatus::Cancelling);
+
+        let queue =
+            Arc::new(JobQueue::open(Arc::new(PipelineManager::new()), settings(2), &path).unwrap());
+        queue.resume();
+        let job = wait_until_finished(&queue, job.id).await;
+        assert_eq!(job.status, JobStatus::Cancelled);
+        assert_eq!(job.processed, processed);
+        assert!(job.processed < 10);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    // stands in for a client's webhook endpoint, failing the first calls
+    struct Receiver {
+        failures: std::sync::atomic::AtomicUsize,
+        received: Mutex<Vec<(actix_web::http::header::HeaderMap, actix_web::web::Bytes)>>,
+    }
+
+    async fn receive(
+        req: actix_web::HttpRequest,
+        body: actix_web::web::Bytes,
+        receiver: actix_web::web::Data<Receiver>,
+    ) -> actix_web::HttpResponse {
+        receiver
+            .received
+            .lock()
+            .unwrap()
+            .push((req.headers().clone(), body));
+        let failing = receiver
+            .failures
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok();
+        if failing {
+            actix_web::HttpResponse::InternalServerError().finish()
+        } else {
+            actix_web::HttpResponse::NoContent().finish()
+        }
+    }
+
+    fn start_receiver(failures: usize) -> (String, actix_web::web::Data<Receiver>) {
+        let receiver = actix_web::web::Data::new(Receiver {
+            failures: failures.into(),
+            received: Mutex::new(Vec::new()),
+        });
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+
+        let data = receiver.clone();
+        let server = actix_web::HttpServer::new(move || {
+            actix_web::App::new()
+                .app_data(data.clone())
+                .route("/hook", actix_web::web::post().to(receive))
+        })
+        .workers(1)
+        .listen(listener)
+        .unwrap()
+        .run();
+        actix_web::rt::spawn(server);
+
+        (url, receiver)
+    }
+
+    #[actix_web::test]
+    async fn test_callback_signed_and_retried() {
+        use crate::infrastructure::webhook::{self, WebhookConfig};
+
+        let (url, receiver) = start_receiver(1);
+        let sender = WebhookSender::new(&WebhookConfig {
+            secret: Some("s3cret".to_string()),
+            initial_backoff_ms: 10,
+            allowed_hosts: vec!["127.0.0.1".to_string()],
+            ..WebhookConfig::default()
+        })
+        .unwrap();
+        let pipeline = PipelineManager::new();
+        let permit = pipeline.admit(2, true).unwrap();
+        let queue = Arc::new(
+            JobQueue::new(Arc::new(pipeline), settings(10))
+                .with_webhooks(sender, "https://signatures.example.com"),
+        );
+
+        let job = queue.submit(signatures(2), Some(url), permit).await;
+        let id = job.id;
+        wait_until_finished(&queue, id).await;
+        let mut job = queue.get(id).unwrap();
+        for _ in 0..100 {
+            if job.deliveries.iter().any(|d| d.delivered) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            job = queue.get(id).unwrap();
+        }
+
+        // the receiver's 500 was retried
+        assert_eq!(job.deliveries.len(), 2);
+        assert_eq!(job.deliveries[0].status, Some(500));
+        assert!(!job.deliveries[0].delivered);
+        assert!(job.deliveries[1].delivered);
+
+        let received = receiver.received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        let (headers, body) = &received[1];
+        assert_eq!(
+            headers.get(webhook::SIGNATURE_HEADER).unwrap(),
+            webhook::sign("s3cret", body).as_str()
+        );
+        assert_eq!(headers.get(webhook::ATTEMPT_HEADER).unwrap(), "2");
+        assert_eq!(
+            headers.get(webhook::DELIVERY_HEADER).unwrap(),
+            id.to_string().as_str()
+        );
+
+        let notification: serde_json::Value = serde_json::from_slice(body).unwrap();
+        assert_eq!(notification["status"], "completed");
+        assert_eq!(notification["summary"]["valid"], 2);
+        assert_eq!(
+            notification["results_url"],
+            format!("https://signatures.example.com/api/v1/jobs/{}", id)
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_subscribers_follow_each_chunk() {
+        let pipeline = PipelineManager::builder()
+            .stage(Arc::new(Slow))
+            .build()
+            .unwrap();
+        let permit = pipeline.admit(3, true).unwrap();
+        let queue = queue(pipeline, 2);
+
+        let mut sigs = signatures(3);
+        sigs[2].email = "invalid".to_string();
+        let job = queue.submit(sigs, None, permit).await;
+        let (_, mut updates) = queue.subscribe(job.id).unwrap();
+
+        let JobUpdate::Progress(first) = updates.recv().await.unwrap() else {
+            panic!("expected progress");
+        };
+        assert_eq!((first.processed, first.total), (2, 3));
+        assert_eq!(first.summary.valid, 2);
+        assert!(first.latest_errors.is_empty());
+
+        // the invalid one doesn't reach the slow stage, its errors show with the second chunk
+        let JobUpdate::Progress(second) = updates.recv().await.unwrap() else {
+            panic!("expected progress");
+        };
+        assert_eq!(second.processed, 3);
+        assert_eq!(second.summary.invalid, 1);
+        assert_eq!(second.latest_errors.len(), 1);
+        assert_eq!(second.latest_errors[0].errors[0].field, "email");
+
+        let JobUpdate::Finished(summary) = updates.recv().await.unwrap() else {
+            panic!("expected the summary");
+        };
+        assert_eq!(summary.status, JobStatus::Completed);
+        assert_eq!(summary.summary.total, 3);
+    }
+}
@@ -0,0 +1,360 @@
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::future::Future;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::domain::{EmailSignature, ValidationResult};
+use crate::infrastructure::webhook::DeliveryAttempt;
+use crate::pipeline::jobs::{DeadLetter, Job, JobStatus};
+
+// one line of the journal, a job is rebuilt by replaying its events in order
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum JobEvent {
+    Submitted {
+        id: Uuid,
+        created_at: DateTime<Utc>,
+        total: usize,
+        // dropped once the job is finished and compacted
+        #[serde(default)]
+        signatures: Vec<EmailSignature>,
+        #[serde(default)]
+        callback_url: Option<String>,
+    },
+    // a chunk went through the pipeline, everything before `processed` is done
+    Chunk {
+        id: Uuid,
+        processed: usize,
+        results: Vec<ValidationResult>,
+        #[serde(default)]
+        dead_letters: Vec<DeadLetter>,
+    },
+    // asked to stop, a job still cancelling after a restart is finished as cancelled
+    Cancelling {
+        id: Uuid,
+    },
+    Finished {
+        id: Uuid,
+        status: JobStatus,
+        finished_at: DateTime<Utc>,
+    },
+    // a try at calling the job's callback url
+    Delivery {
+        id: Uuid,
+        attempt: DeliveryAttempt,
+    },
+}
+
+// a job as it stood when the journal was last written
+#[derive(Clone)]
+pub struct RecoveredJob {
+    pub job: Job,
+    pub signatures: Vec<EmailSignature>,
+}
+
+// append-only record of batch jobs, replayed and compacted on startup and again each time
+// as many jobs finished as are retained, so it doesn't grow with every job while running
+pub struct JobJournal {
+    // to the thread writing the file, so syncing to disk never blocks the executor
+    writer: mpsc::Sender<Append>,
+}
+
+struct Append {
+    line: Vec<u8>,
+    written: oneshot::Sender<io::Result<()>>,
+}
+
+impl JobJournal {
+    // replay what's recorded, keeping at most `retain_finished` finished jobs
+    pub fn open(
+        path: impl AsRef<Path>,
+        retain_finished: usize,
+    ) -> anyhow::Result<(Self, Vec<RecoveredJob>)> {
+        let path = path.as_ref();
+        let mut replayed = if path.exists() {
+            replay(path)?
+        } else {
+            Replayed::default()
+        };
+        replayed.retain(retain_finished);
+
+        compact(path, &replayed)?;
+        let file = open_for_append(path)?;
+        let jobs = replayed.jobs().cloned().collect();
+
+        let writer = Writer {
+            path: path.to_path_buf(),
+            file,
+            replayed,
+            retain_finished,
+            finished_since_compaction: 0,
+        };
+        Ok((
+            Self {
+                writer: writer.spawn()?,
+            },
+            jobs,
+        ))
+    }
+
+    // events are written in the order this is called, the returned future resolves once
+    // the event is on disk, so a recorded chunk isn't run again
+    pub fn append(&self, event: &JobEvent) -> impl Future<Output = anyhow::Result<()>> + use<> {
+        let queued = serde_json::to_vec(event)
+            .map_err(anyhow::Error::from)
+            .and_then(|mut line| {
+                line.push(b'\n');
+                let (written, done) = oneshot::channel();
+                self.writer
+                    .send(Append { line, written })
+                    .map_err(|_| anyhow::anyhow!("Job journal writer stopped"))?;
+                Ok(done)
+            });
+
+        async move {
+            queued?
+                .await
+                .map_err(|_| anyhow::anyhow!("Job journal writer stopped"))??;
+            Ok(())
+        }
+    }
+}
+
+fn open_for_append(path: &Path) -> anyhow::Result<File> {
+    OpenOptions::new()
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open job journal {}", path.display()))
+}
+
+// owns the file, keeping its own replay of it to compact from
+struct Writer {
+    path: PathBuf,
+    file: File,
+    replayed: Replayed,
+    retain_finished: usize,
+    finished_since_compaction: usize,
+}
+
+impl Writer {
+    // appends whatever is queued, then syncs once for all of it
+    fn spawn(mut self) -> anyhow::Result<mpsc::Sender<Append>> {
+        let (tx, rx) = mpsc::channel::<Append>();
+        std::thread::Builder::new()
+            .name("job-journal".to_string())
+            .spawn(move || {
+                while let Ok(first) = rx.recv() {
+                    let batch: Vec<Append> = std::iter::once(first).chain(rx.try_iter()).collect();
+                    let mut result = Ok(());
+                    for append in &batch {
+                        result = result.and_then(|_| self.file.write_all(&append.line));
+                    }
+                    let result = result.and_then(|_| self.file.sync_data());
+
+                    for append in batch {
+                        if result.is_ok() {
+                            self.apply(&append.line);
+                        }
+                        let outcome = match &result {
+                            Ok(()) => Ok(()),
+                            Err(e) => Err(io::Error::new(e.kind(), e.to_string())),
+                        };
+                        let _ = append.written.send(outcome);
+                    }
+                    self.compact_if_due();
+                }
+            })
+            .context("Failed to start the job journal writer")?;
+        Ok(tx)
+    }
+
+    fn apply(&mut self, line: &[u8]) {
+        // written by `append`, so it always reads back
+        if let Ok(event) = serde_json::from_slice::<JobEvent>(line) {
+            if matches!(event, JobEvent::Finished { .. }) {
+                self.finished_since_compaction += 1;
+            }
+            self.replayed.apply(event);
+        }
+    }
+
+    // once as many jobs finished as are retained, so the file holds at most twice that
+    fn compact_if_due(&mut self) {
+        if self.finished_since_compaction < self.retain_finished.max(1) {
+            return;
+        }
+        self.finished_since_compaction = 0;
+        self.replayed.retain(self.retain_finished);
+
+        let compacted =
+            compact(&self.path, &self.replayed).and_then(|_| open_for_append(&self.path));
+        match compacted {
+            Ok(file) => self.file = file,
+            // keeps appending to the file as it was, it's compacted on the next startup
+            Err(e) => warn!(error = %e, "Failed to compact job journal"),
+        }
+    }
+}
+
+// jobs rebuilt from the journal, in the order they were submitted
+#[derive(Default)]
+struct Replayed {
+    order: Vec<Uuid>,
+    jobs: HashMap<Uuid, RecoveredJob>,
+}
+
+impl Replayed {
+    fn jobs(&self) -> impl Iterator<Item = &RecoveredJob> {
+        self.order.iter().filter_map(|id| self.jobs.get(id))
+    }
+
+    fn apply(&mut self, event: JobEvent) {
+        let jobs = &mut self.jobs;
+        match event {
+            JobEvent::Submitted {
+                id,
+                created_at,
+                total,
+                signatures,
+                callback_url,
+            } => {
+                let mut job = Job::new(id, total, created_at);
+                job.callback_url = callback_url;
+                self.order.push(id);
+                jobs.insert(id, RecoveredJob { job, signatures });
+            }
+            JobEvent::Chunk {
+                id,
+                processed,
+                results,
+                dead_letters,
+            } => {
+                if let Some(recovered) = jobs.get_mut(&id) {
+                    recovered.job.processed = processed;
+                    recovered.job.results.extend(results);
+                    recovered.job.dead_letters.extend(dead_letters);
+                }
+            }
+            JobEvent::Cancelling { id } => {
+                // may be written after the job finished, it's too late by then
+                if let Some(recovered) = jobs.get_mut(&id)
+                    && !recovered.job.status.is_finished()
+                {
+                    recovered.job.status = JobStatus::Cancelling;
+                }
+            }
+            JobEvent::Finished {
+                id,
+                status,
+                finished_at,
+            } => {
+                if let Some(recovered) = jobs.get_mut(&id) {
+                    recovered.job.status = status;
+                    recovered.job.finished_at = Some(finished_at);
+                    recovered.signatures = Vec::new();
+                }
+            }
+            JobEvent::Delivery { id, attempt } => {
+                if let Some(recovered) = jobs.get_mut(&id) {
+                    recovered.job.deliveries.push(attempt);
+                }
+            }
+        }
+    }
+
+    // keep at most `retain_finished` finished jobs, the most recently finished ones
+    fn retain(&mut self, retain_finished: usize) {
+        let mut finished: Vec<DateTime<Utc>> = self
+            .jobs
+            .values()
+            .filter_map(|r| r.job.finished_at)
+            .collect();
+        if finished.len() > retain_finished {
+            finished.sort();
+            let cutoff = finished[finished.len() - retain_finished - 1];
+            self.jobs
+                .retain(|_, r| r.job.finished_at.is_none_or(|at| at > cutoff));
+            self.order.retain(|id| self.jobs.contains_key(id));
+        }
+    }
+}
+
+fn replay(path: &Path) -> anyhow::Result<Replayed> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to read job journal {}", path.display()))?;
+    let mut replayed = Replayed::default();
+
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        // the last line may be cut short by a crash, its chunk just runs again
+        match serde_json::from_str(&line) {
+            Ok(event) => replayed.apply(event),
+            Err(e) => {
+                warn!(line = number + 1, error = %e, "Skipping unreadable job journal entry");
+            }
+        }
+    }
+
+    Ok(replayed)
+}
+
+// rewrite the journal with one entry per event still needed, via a temporary file
+fn compact(path: &Path, replayed: &Replayed) -> anyhow::Result<()> {
+    let tmp = path.with_extension("compacting");
+    let mut out = Vec::new();
+
+    for recovered in replayed.jobs() {
+        let job = &recovered.job;
+        let mut events = vec![JobEvent::Submitted {
+            id: job.id,
+            created_at: job.created_at,
+            total: job.total,
+            signatures: recovered.signatures.clone(),
+            callback_url: job.callback_url.clone(),
+        }];
+        if job.processed > 0 {
+            events.push(JobEvent::Chunk {
+                id: job.id,
+                processed: job.processed,
+                results: job.results.clone(),
+                dead_letters: job.dead_letters.clone(),
+            });
+        }
+        if job.status == JobStatus::Cancelling {
+            events.push(JobEvent::Cancelling { id: job.id });
+        }
+        if let Some(finished_at) = job.finished_at {
+            events.push(JobEvent::Finished {
+                id: job.id,
+                status: job.status,
+                finished_at,
+            });
+        }
+        events.extend(job.deliveries.iter().map(|attempt| JobEvent::Delivery {
+            id: job.id,
+            attempt: attempt.clone(),
+        }));
+
+        for event in events {
+            serde_json::to_writer(&mut out, &event)?;
+            out.push(b'\n');
+        }
+    }
+
+    fs::write(&tmp, out)
+        .with_context(|| format!("Failed to write job journal {}", tmp.display()))?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
@@ -1,23 +1,666 @@
 use std::sync::Arc;
+use std::time::Instant;
 
-use crate::domain::{EmailSignature, SignatureValidator, ValidationResult};
+use tracing::debug;
 
+use crate::domain::{
+    EmailSignature, LinkValidator, SignatureValidator, ValidationError, ValidationResult,
+    duplicates,
+};
+use crate::error::AppError;
+use crate::infrastructure::{SignatureStore, metrics};
+use crate::pipeline::admission::{Admission, AdmissionLimits, Permit};
+use crate::pipeline::cache::ValidationCache;
+use crate::pipeline::pool::{Lane, WorkerPool};
+use crate::pipeline::stage::{Cancellation, Deadlines, PipelineItem, RunContext, Stage};
+use crate::pipeline::stages::{
+    EnrichStage, NormalizeStage, PersistStage, RenderStage, STAGE_NAMES, VALIDATION_STAGES,
+    ValidateStage,
+};
+use crate::rendering::TemplateRegistry;
+
+// stages run when none are configured
+pub const DEFAULT_STAGES: &[&str] = &["normalize", "validate"];
+
+// runs signatures through an ordered list of stages
 pub struct PipelineManager {
     validator: Arc<SignatureValidator>,
+    stages: Vec<Arc<dyn Stage>>,
+    // the stages without enrich, render and persist, for callers that mustn't render or store
+    validation: Vec<Arc<dyn Stage>>,
+    admission: Arc<Admission>,
+    deadlines: Deadlines,
+    pool: Arc<WorkerPool>,
 }
 
 impl PipelineManager {
     pub fn new() -> Self {
-        Self {
-            validator: Arc::new(SignatureValidator::new()),
-        }
+        Self::builder()
+            .build()
+            .expect("default stages have no dependencies")
+    }
+
+    pub fn builder() -> PipelineBuilder {
+        PipelineBuilder::default()
+    }
+
+    pub fn stage_names(&self) -> Vec<&'static str> {
+        self.stages.iter().map(|stage| stage.name()).collect()
+    }
+
+    // whether a full run stores the signatures that pass
+    pub fn persists(&self) -> bool {
+        self.stage_names().contains(&"persist")
+    }
+
+    // room for a request's signatures, held until the response is ready
+    pub fn admit(&self, signatures: usize, batch: bool) -> Result<Permit, AppError> {
+        self.admission.admit(signatures, batch)
+    }
+
+    // changes whenever the outcome of validating the same signature could
+    pub fn rules_version(&self) -> String {
+        self.validator.rules_version()
+    }
+
+    pub fn normalize(&self, sig: &mut EmailSignature) {
+        self.validator.normalize(sig);
     }
 
     pub async fn process_single(&self, sig: EmailSignature) -> ValidationResult {
-        self.validator.validate(&sig)
+        let ctx = self.context(&Cancellation::new(), Lane::Interactive);
+        self.run_stages(&self.validation, numbered(vec![sig]), ctx)
+            .await
+            .pop()
+            .expect("one result per signature")
+            .result
     }
 
     pub async fn process_batch(&self, sigs: Vec<EmailSignature>) -> Vec<ValidationResult> {
-        self.validator.validate_batch(&sigs)
+        self.validate_cancellable(sigs, &Cancellation::new())
+            .await
+            .into_iter()
+            .map(|item| item.result)
+            .collect()
+    }
+
+    // like process_batch, without the results of signatures the cancellation cut off
+    pub async fn process_batch_cancellable(
+        &self,
+        sigs: Vec<EmailSignature>,
+        cancellation: &Cancellation,
+    ) -> Vec<ValidationResult> {
+        self.validate_cancellable(sigs, cancellation)
+            .await
+            .into_iter()
+            .filter(|item| !item.is_cancelled())
+            .map(|item| item.result)
+            .collect()
+    }
+
+    // every configured stage for one signature, not queued behind batches
+    pub async fn run_single(&self, sig: EmailSignature) -> PipelineItem {
+        let ctx = self.context(&Cancellation::new(), Lane::Interactive);
+        self.run_stages(&self.stages, numbered(vec![sig]), ctx)
+            .await
+            .pop()
+            .expect("one item per signature")
+    }
+
+    pub async fn run(&self, sigs: Vec<EmailSignature>) -> Vec<PipelineItem> {
+        self.run_cancellable(sigs, &Cancellation::new()).await
+    }
+
+    // every configured stage, rendering and storing signatures if set up to
+    pub async fn run_cancellable(
+        &self,
+        sigs: Vec<EmailSignature>,
+        cancellation: &Cancellation,
+    ) -> Vec<PipelineItem> {
+        let ctx = self.context(cancellation, Lane::Batch);
+        self.run_stages(&self.stages, numbered(sigs), ctx).await
+    }
+
+    // only the validation stages, what the process_ methods report on
+    pub async fn validate_cancellable(
+        &self,
+        sigs: Vec<EmailSignature>,
+        cancellation: &Cancellation,
+    ) -> Vec<PipelineItem> {
+        let ctx = self.context(cancellation, Lane::Batch);
+        self.run_stages(&self.validation, numbered(sigs), ctx).await
+    }
+
+    // in-batch duplicates of a batch validated in parts, for `validate_part_cancellable`
+    pub async fn find_duplicates(&self, sigs: &[EmailSignature]) -> Vec<Vec<ValidationError>> {
+        let sigs = sigs.to_vec();
+        self.pool
+            .run(Lane::Batch, move || {
+                let sigs: Vec<(usize, &EmailSignature)> = sigs.iter().enumerate().collect();
+                duplicates::find_duplicates(&sigs)
+            })
+            .await
+    }
+
+    // like validate_cancellable for part of a larger batch, signatures keep their position in it
+    // and duplicates are the ones found across all of it
+    pub async fn validate_part_cancellable(
+        &self,
+        sigs: Vec<(usize, EmailSignature)>,
+        duplicates: Arc<Vec<Vec<ValidationError>>>,
+        cancellation: &Cancellation,
+    ) -> Vec<PipelineItem> {
+        let items = sigs
+            .into_iter()
+            .map(|(index, sig)| PipelineItem::new(index, sig))
+            .collect();
+        let mut ctx = self.context(cancellation, Lane::Batch);
+        ctx.duplicates = Some(duplicates);
+        self.run_stages(&self.validation, items, ctx).await
+    }
+
+    fn context(&self, cancellation: &Cancellation, lane: Lane) -> RunContext {
+        RunContext::new(&self.deadlines, cancellation.clone(), lane)
+    }
+
+    // run stages in order, each over the items no earlier stage halted
+    async fn run_stages(
+        &self,
+        stages: &[Arc<dyn Stage>],
+        mut items: Vec<PipelineItem>,
+        ctx: RunContext,
+    ) -> Vec<PipelineItem> {
+        for stage in stages {
+            let mut active: Vec<&mut PipelineItem> =
+                items.iter_mut().filter(|item| !item.is_halted()).collect();
+            if active.is_empty() {
+                break;
+            }
+
+            if ctx.is_cancelled() {
+                active.iter_mut().for_each(|item| item.cancel());
+                break;
+            }
+
+            // out of time, whatever hasn't finished yet comes back timed out
+            if ctx.expired() {
+                active
+                    .iter_mut()
+                    .for_each(|item| item.time_out(stage.name()));
+                break;
+            }
+
+            let start = Instant::now();
+            stage.process(&mut active, &ctx).await;
+            let elapsed = start.elapsed();
+
+            metrics::record_stage_duration(stage.name(), elapsed.as_secs_f64());
+            debug!(
+                stage = stage.name(),
+                items = active.len(),
+                elapsed_ms = elapsed.as_millis() as u64,
+                "Pipeline stage finished"
+            );
+        }
+
+        items
+    }
+}
+
+// items for a whole batch, numbered by position
+fn numbered(sigs: Vec<EmailSignature>) -> Vec<PipelineItem> {
+    sigs.into_iter()
+        .enumerate()
+        .map(|(index, sig)| PipelineItem::new(index, sig))
+        .collect()
+}
+
+impl Default for PipelineManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// what the stages are built from, and which of them run
+pub struct PipelineBuilder {
+    validator: SignatureValidator,
+    links: Option<LinkValidator>,
+    store: Option<Arc<dyn SignatureStore>>,
+    cache: Option<Arc<dyn ValidationCache>>,
+    templates: Option<Arc<TemplateRegistry>>,
+    workers: usize,
+    queue_depth: usize,
+    limits: AdmissionLimits,
+    deadlines: Deadlines,
+    stages: Vec<String>,
+    custom: Vec<Arc<dyn Stage>>,
+}
+
+impl Default for PipelineBuilder {
+    fn default() -> Self {
+        Self {
+            validator: SignatureValidator::new(),
+            links: None,
+            store: None,
+            cache: None,
+            templates: None,
+            workers: std::thread::available_parallelism().map_or(4, |n| n.get()),
+            queue_depth: 16,
+            limits: AdmissionLimits::default(),
+            deadlines: Deadlines::default(),
+            stages: DEFAULT_STAGES.iter().map(|s| s.to_string()).collect(),
+            custom: Vec::new(),
+        }
+    }
+}
+
+impl PipelineBuilder {
+    pub fn validator(mut self, validator: SignatureValidator) -> Self {
+        self.validator = validator;
+        self
+    }
+
+    // enable reachability checks on signature links
+    pub fn link_validator(mut self, links: LinkValidator) -> Self {
+        self.links = Some(links);
+        self
+    }
+
+    // flag batch entries whose email is taken by another stored signature,
+    // and where the persist stage writes to
+    pub fn store(mut self, store: Arc<dyn SignatureStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    // skip validating signatures whose content was already validated under the same rules
+    pub fn cache(mut self, cache: Arc<dyn ValidationCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    // needed by the enrich and render stages
+    pub fn templates(mut self, templates: Arc<TemplateRegistry>) -> Self {
+        self.templates = Some(templates);
+        self
+    }
+
+    // threads validating batches, apart from the async executor
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers;
+        self
+    }
+
+    // batch jobs queued on the workers, more wait without holding a place in their queue
+    pub fn queue_depth(mut self, depth: usize) -> Self {
+        self.queue_depth = depth;
+        self
+    }
+
+    // how much work is accepted before requests are turned away
+    pub fn limits(mut self, limits: AdmissionLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn deadlines(mut self, deadlines: Deadlines) -> Self {
+        self.deadlines = deadlines;
+        self
+    }
+
+    // built-in stages by name, run in the given order
+    pub fn stages(mut self, names: &[String]) -> Self {
+        self.stages = names.to_vec();
+        self
+    }
+
+    // a stage of our own, run after the built-in ones, also when only validating
+    pub fn stage(mut self, stage: Arc<dyn Stage>) -> Self {
+        self.custom.push(stage);
+        self
+    }
+
+    pub fn build(self) -> anyhow::Result<PipelineManager> {
+        let validator = Arc::new(self.validator);
+        let pool = Arc::new(WorkerPool::new(self.workers, self.queue_depth)?);
+        let mut links = self.links;
+        let mut stages: Vec<Arc<dyn Stage>> = Vec::new();
+
+        for name in &self.stages {
+            let stage: Arc<dyn Stage> = match name.as_str() {
+                "normalize" => Arc::new(NormalizeStage::new(validator.clone())),
+                "validate" => Arc::new(ValidateStage::new(
+                    validator.clone(),
+                    pool.clone(),
+                    links.take(),
+                    self.store.clone(),
+                    self.cache.clone(),
+                )),
+                "enrich" => Arc::new(EnrichStage::new(required(&self.templates, name)?)),
+                "render" => Arc::new(RenderStage::new(required(&self.templates, name)?)),
+                "persist" => Arc::new(PersistStage::new(required(&self.store, name)?)),
+                _ => anyhow::bail!(
+                    "Unknown pipeline stage {}, expected one of {}",
+                    name,
+                    STAGE_NAMES.join(", ")
+                ),
+            };
+            stages.push(stage);
+        }
+
+        let mut validation: Vec<Arc<dyn Stage>> = stages
+            .iter()
+            .filter(|stage| VALIDATION_STAGES.contains(&stage.name()))
+            .cloned()
+            .collect();
+        validation.extend(self.custom.iter().cloned());
+        stages.extend(self.custom);
+        Ok(PipelineManager {
+            validator,
+            stages,
+            validation,
+            admission: Arc::new(Admission::new(self.limits)),
+            deadlines: self.deadlines,
+            pool,
+        })
+    }
+}
+
+fn required<T: ?Sized>(dependency: &Option<Arc<T>>, stage: &str) -> anyhow::Result<Arc<T>> {
+    dependency
+        .clone()
+        .ok_or_else(|| anyhow::anyhow!("Pipeline stage {} is missing a dependency", stage))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{ErrorCode, StubProber};
+    use crate::infrastructure::InMemorySignatureStore;
+    use crate::pipeline::LruValidationCache;
+    use std::time::Duration;
+
+    #[actix_web::test]
+    async fn test_unreachable_link_invalidates_result() {
+        let pipeline = PipelineManager::builder()
+            .link_validator(LinkValidator::new(
+                StubProber::new().unreachable("https://example.com/missing"),
+            ))
+            .build()
+            .unwrap();
+        let sig = EmailSignature::builder()
+            .website("https://example.com/missing")
+            .build();
+
+        let results = pipeline.process_batch(vec![sig]).await;
+        assert!(!results[0].valid);
+        assert_eq!(results[0].errors[0].field, "website");
+    }
+
+    #[actix_web::test]
+    async fn test_duplicates_against_store() {
+        let store = Arc::new(InMemorySignatureStore::new());
+        let stored = EmailSignature::builder().email("jane@example.com").build();
+        store.put(stored.clone());
+        let pipeline = PipelineManager::builder().store(store).build().unwrap();
+
+        let results = pipeline
+            .process_batch(vec![
+                EmailSignature::builder().email("JANE@example.com").build(),
+                stored.clone(),
+            ])
+            .await;
+
+        // the stored signature itself isn't its own duplicate, but it collides within the batch
+        assert_eq!(results[0].errors.len(), 2);
+        assert!(matches!(results[0].errors[1].code, ErrorCode::Duplicate));
+        assert_eq!(
+            results[0].errors[1].params["signature_id"],
+            stored.id.to_string()
+        );
+        assert_eq!(results[1].errors.len(), 1);
+        assert_eq!(results[1].errors[0].params["indexes"], "0");
+    }
+
+    #[actix_web::test]
+    async fn test_cached_result_reused() {
+        let cache = Arc::new(LruValidationCache::new(
+            10,
+            std::time::Duration::from_secs(60),
+        ));
+        let pipeline = PipelineManager::builder()
+            .cache(cache.clone())
+            .build()
+            .unwrap();
+        let sig = EmailSignature::builder().email("invalid").build();
+
+        let first = pipeline.process_single(sig.clone()).await;
+        let mut renamed = sig.clone();
+        renamed.id = uuid::Uuid::new_v4();
+        let second = pipeline.process_single(renamed.clone()).await;
+
+        assert_eq!(cache.len(), 1);
+        assert_eq!(second.signature_id, renamed.id);
+        assert_eq!(second.errors.len(), first.errors.len());
+    }
+
+    struct Counter(std::sync::atomic::AtomicUsize);
+
+    #[async_trait::async_trait]
+    impl Stage for Counter {
+        fn name(&self) -> &'static str {
+            "counter"
+        }
+
+        async fn process(&self, items: &mut [&mut PipelineItem], _ctx: &RunContext) {
+            self.0
+                .fetch_add(items.len(), std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_stages_short_circuit_invalid_signatures() {
+        let store = Arc::new(InMemorySignatureStore::new());
+        let counter = Arc::new(Counter(Default::default()));
+        let stages: Vec<String> = STAGE_NAMES.iter().map(|s| s.to_string()).collect();
+        let pipeline = PipelineManager::builder()
+            .store(store.clone())
+            .templates(Arc::new(TemplateRegistry::new()))
+            .stages(&stages)
+            .stage(counter.clone())
+            .build()
+            .unwrap();
+
+        let valid = EmailSignature::builder().email("jane@example.com").build();
+        let invalid = EmailSignature::builder().email("invalid").build();
+        let items = pipeline.run(vec![valid.clone(), invalid.clone()]).await;
+
+        // the valid one was enriched onto the default template, rendered and stored
+        assert!(
+            items[0]
+                .html
+                .as_deref()
+                .unwrap()
+                .contains("jane@example.com")
+        );
+        assert_eq!(items[0].result.warnings.len(), 1);
+        assert!(store.get(valid.id).is_some());
+
+        // the invalid one stopped after validation
+        assert!(items[1].is_halted());
+        assert!(items[1].html.is_none());
+        assert!(store.get(invalid.id).is_none());
+        assert_eq!(counter.0.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        // validating alone neither renders nor stores
+        let checked = EmailSignature::builder().email("john@example.com").build();
+        let results = pipeline.process_batch(vec![checked.clone()]).await;
+        assert!(results[0].valid && results[0].warnings.is_empty());
+        assert!(store.get(checked.id).is_none());
+        assert_eq!(counter.0.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_stage_configuration_checked() {
+        let stages = |names: &[&str]| names.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert!(
+            PipelineManager::builder()
+                .stages(&stages(&["render"]))
+                .build()
+                .is_err()
+        );
+        assert!(
+            PipelineManager::builder()
+                .stages(&stages(&["translate"]))
+                .build()
+                .is_err()
+        );
+        assert_eq!(PipelineManager::new().stage_names(), DEFAULT_STAGES);
+    }
+
+    struct Slow(Duration);
+
+    #[async_trait::async_trait]
+    impl Stage for Slow {
+        fn name(&self) -> &'static str {
+            "slow"
+        }
+
+        async fn process(&self, _items: &mut [&mut PipelineItem], _ctx: &RunContext) {
+            tokio::time::sleep(self.0).await;
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_batch_deadline_times_out_unfinished_signatures() {
+        let counter = Arc::new(Counter(Default::default()));
+        let pipeline = PipelineManager::builder()
+            .deadlines(Deadlines {
+                signature_ms: 5000,
+                batch_ms: 20,
+            })
+            .stage(Arc::new(Slow(Duration::from_millis(50))))
+            .stage(counter.clone())
+            .build()
+            .unwrap();
+
+        let valid = EmailSignature::builder().email("jane@example.com").build();
+        let invalid = EmailSignature::builder().email("invalid").build();
+        let results = pipeline.process_batch(vec![valid, invalid]).await;
+
+        // the valid one ran out of time before the last stage, the invalid one had already stopped
+        assert!(!results[0].valid);
+        let timeout = results[0].errors.last().unwrap();
+        assert!(matches!(timeout.code, ErrorCode::Timeout));
+        assert_eq!(timeout.params.get("stage").unwrap(), "counter");
+        assert!(
+            !results[1]
+                .errors
+                .iter()
+                .any(|e| matches!(e.code, ErrorCode::Timeout))
+        );
+        assert_eq!(counter.0.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    struct SlowRule(Duration);
+
+    impl crate::domain::CrossFieldRule for SlowRule {
+        fn check(&self, _sig: &EmailSignature, _errors: &mut Vec<crate::domain::ValidationError>) {
+            std::thread::sleep(self.0);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_batch_deadline_bounds_validation() {
+        let pipeline = PipelineManager::builder()
+            .validator(
+                SignatureValidator::new().with_rule(Arc::new(SlowRule(Duration::from_millis(100)))),
+            )
+            .workers(1)
+            .deadlines(Deadlines {
+                signature_ms: 5000,
+                batch_ms: 50,
+            })
+            .build()
+            .unwrap();
+
+        let start = std::time::Instant::now();
+        let results = pipeline
+            .process_batch((0..5).map(|_| EmailSignature::builder().build()).collect())
+            .await;
+
+        // answered at the deadline, not after the pool worked through all five
+        assert!(start.elapsed() < Duration::from_millis(300));
+        assert!(results.iter().all(|result| {
+            result
+                .errors
+                .iter()
+                .any(|e| e.code == ErrorCode::Timeout && e.params["stage"] == "validate")
+        }));
+    }
+
+    struct Hanging;
+
+    #[async_trait::async_trait]
+    impl crate::domain::LinkProber for Hanging {
+        async fn probe(&self, _url: &url::Url) -> crate::domain::ProbeOutcome {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            crate::domain::ProbeOutcome::Reachable
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_invalid_signature_not_reported_as_timed_out() {
+        let pipeline = PipelineManager::builder()
+            .link_validator(LinkValidator::new(Hanging))
+            .deadlines(Deadlines {
+                signature_ms: 20,
+                batch_ms: 5000,
+            })
+            .build()
+            .unwrap();
+        let sig = |email: &str| {
+            EmailSignature::builder()
+                .email(email)
+                .website("https://example.com")
+                .build()
+        };
+
+        let results = pipeline
+            .process_batch(vec![
+                sig("jane@example.com"),
+                sig("jane@example.com"),
+                sig("john@example.com"),
+            ])
+            .await;
+
+        // the duplicates are invalid either way, only the valid one ran out of time
+        for result in &results[..2] {
+            assert!(!result.valid);
+            assert!(!result.errors.iter().any(|e| e.code == ErrorCode::Timeout));
+        }
+        assert!(matches!(
+            results[2].errors.as_slice(),
+            [e] if e.code == ErrorCode::Timeout
+        ));
+    }
+
+    #[actix_web::test]
+    async fn test_cancelled_run_reports_nothing() {
+        let pipeline = PipelineManager::new();
+        let cancellation = Cancellation::new();
+        drop(cancellation.guard());
+
+        let items = pipeline
+            .run_cancellable(vec![EmailSignature::builder().build()], &cancellation)
+            .await;
+        assert!(items[0].is_cancelled());
+        assert!(
+            pipeline
+                .process_batch_cancellable(vec![EmailSignature::builder().build()], &cancellation)
+                .await
+                .is_empty()
+        );
     }
 }
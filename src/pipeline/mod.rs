@@ -1,2 +1,18 @@
+pub mod admission;
+pub mod cache;
+pub mod jobs;
+pub mod journal;
 pub mod manager;
-pub use manager::PipelineManager;
+pub mod pool;
+pub mod revalidation;
+pub mod stage;
+pub mod stages;
+pub use admission::{AdmissionLimits, Permit};
+pub use cache::{LruValidationCache, ValidationCache};
+pub use jobs::{
+    DeadLetter, Job, JobNotification, JobProgress, JobQueue, JobSettings, JobStatus, JobUpdate,
+};
+pub use manager::{PipelineBuilder, PipelineManager};
+pub use pool::{Lane, WorkerPool};
+pub use revalidation::{RevalidationReport, Revalidator};
+pub use stage::{CancelOnDrop, Cancellation, Deadlines, PipelineItem, RunContext, Stage};
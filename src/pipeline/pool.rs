@@ -0,0 +1,144 @@
+use std::panic::{AssertUnwindSafe, catch_unwind, resume_unwind};
+use std::sync::Arc;
+
+use tokio::sync::{Semaphore, oneshot};
+
+// where a job runs, single requests aren't queued behind batches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lane {
+    // on the caller's thread, one signature takes less time than handing it to another thread
+    // would on a busy machine
+    Interactive,
+    Batch,
+}
+
+// threads for CPU-bound batch work, kept off the async executor so
+// a large batch doesn't stall the requests sharing its worker thread
+pub struct WorkerPool {
+    pool: rayon::ThreadPool,
+    // jobs running or waiting on the pool, later ones wait outside of its unbounded queue
+    slots: Arc<Semaphore>,
+}
+
+impl WorkerPool {
+    pub fn new(threads: usize, queue_depth: usize) -> anyhow::Result<Self> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|i| format!("pipeline-worker-{}", i))
+            .build()?;
+
+        Ok(Self {
+            pool,
+            slots: Arc::new(Semaphore::new(threads + queue_depth)),
+        })
+    }
+
+    pub fn threads(&self) -> usize {
+        self.pool.current_num_threads()
+    }
+
+    // run a batch job on the pool and wait for it without blocking the caller's thread,
+    // parallel iterators inside the job stay on the pool
+    pub async fn run<T, F>(&self, lane: Lane, job: F) -> T
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        if lane == Lane::Interactive {
+            return job();
+        }
+
+        let slot = self
+            .slots
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("worker pool slots are never closed");
+
+        let (tx, rx) = oneshot::channel();
+        self.pool.spawn(move || {
+            let _ = tx.send(catch_unwind(AssertUnwindSafe(job)));
+            drop(slot);
+        });
+
+        match rx.await.expect("worker pool dropped a job") {
+            Ok(value) => value,
+            Err(panic) => resume_unwind(panic),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rayon::prelude::*;
+
+    #[actix_web::test]
+    async fn test_jobs_run_on_the_pool() {
+        let pool = WorkerPool::new(2, 4).unwrap();
+        assert_eq!(pool.threads(), 2);
+
+        let names = pool
+            .run(Lane::Batch, || {
+                (0..64)
+                    .into_par_iter()
+                    .map(|_| {
+                        std::thread::current()
+                            .name()
+                            .unwrap_or_default()
+                            .to_string()
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .await;
+        assert!(
+            names
+                .iter()
+                .all(|name| name.starts_with("pipeline-worker-"))
+        );
+    }
+
+    #[actix_web::test]
+    async fn test_queue_bounded() {
+        let pool = Arc::new(WorkerPool::new(1, 1).unwrap());
+        let (release, hold) = std::sync::mpsc::channel::<()>();
+        let hold = Arc::new(std::sync::Mutex::new(hold));
+        let started = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+        let jobs: Vec<_> = (0..3)
+            .map(|_| {
+                let (pool, hold, started) = (pool.clone(), hold.clone(), started.clone());
+                actix_web::rt::spawn(async move {
+                    pool.run(Lane::Batch, move || {
+                        started.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                        hold.lock().unwrap().recv().unwrap()
+                    })
+                    .await
+                })
+            })
+            .collect();
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+
+        // one running, one queued on the pool, the third waits for a slot
+        assert_eq!(pool.slots.available_permits(), 0);
+        assert_eq!(started.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        // single requests don't wait for any of them
+        assert_eq!(pool.run(Lane::Interactive, || 42).await, 42);
+
+        for _ in 0..3 {
+            release.send(()).unwrap();
+        }
+        for job in jobs {
+            job.await.unwrap();
+        }
+        assert_eq!(started.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[actix_web::test]
+    #[should_panic(expected = "boom")]
+    async fn test_panics_reach_the_caller() {
+        let pool = WorkerPool::new(1, 0).unwrap();
+        pool.run(Lane::Batch, || panic!("boom")).await
+    }
+}
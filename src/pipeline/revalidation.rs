@@ -0,0 +1,386 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, RwLock};
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::domain::{ErrorCode, ValidationError, ValidationResult};
+use crate::infrastructure::{SignatureStore, metrics};
+use crate::pipeline::manager::PipelineManager;
+
+// cron expression, either the usual five fields or with seconds (and years) added
+pub fn parse_schedule(expr: &str) -> anyhow::Result<Schedule> {
+    let expr = expr.trim();
+    let expr = if expr.split_whitespace().count() == 5 {
+        format!("0 {}", expr)
+    } else {
+        expr.to_string()
+    };
+
+    Schedule::from_str(&expr).map_err(|e| anyhow::anyhow!("Invalid schedule {}: {}", expr, e))
+}
+
+// outcome of the latest check of a stored signature
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignatureStatus {
+    pub signature_id: Uuid,
+    pub valid: bool,
+    pub checked_at: DateTime<Utc>,
+    // when `valid` last flipped, none while it's as it was on submission
+    pub changed_at: Option<DateTime<Utc>>,
+}
+
+// a stored signature whose status flipped in a run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusChange {
+    pub signature_id: Uuid,
+    pub name: String,
+    pub email: String,
+    pub valid: bool,
+    pub errors: Vec<ValidationError>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RevalidationReport {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub rules_version: String,
+    pub checked: usize,
+    // all signatures failing now, including those that already did before this run
+    pub failing: usize,
+    // not finished in time, their status is left as it was
+    #[serde(default)]
+    pub timed_out: usize,
+    // valid before, invalid under the current rules
+    pub newly_failing: Vec<StatusChange>,
+    // invalid before, fine again
+    pub recovered: Vec<StatusChange>,
+}
+
+// re-runs the current rules over stored signatures, on a schedule or on demand
+pub struct Revalidator {
+    pipeline: Arc<PipelineManager>,
+    store: Arc<dyn SignatureStore>,
+    chunk_size: usize,
+    statuses: RwLock<HashMap<Uuid, SignatureStatus>>,
+    last_report: RwLock<Option<RevalidationReport>>,
+    // where statuses and the last report are saved after each run, in memory if unset
+    path: Option<PathBuf>,
+    // one run at a time, a second one waits for the first
+    running: tokio::sync::Mutex<()>,
+}
+
+// what a restart picks up from, so a signature failing before isn't reported as news again
+#[derive(Default, Serialize, Deserialize)]
+struct Saved {
+    statuses: Vec<SignatureStatus>,
+    last_report: Option<RevalidationReport>,
+}
+
+impl Revalidator {
+    pub fn new(
+        pipeline: Arc<PipelineManager>,
+        store: Arc<dyn SignatureStore>,
+        chunk_size: usize,
+    ) -> Self {
+        Self {
+            pipeline,
+            store,
+            chunk_size: chunk_size.max(1),
+            statuses: RwLock::new(HashMap::new()),
+            last_report: RwLock::new(None),
+            path: None,
+            running: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    // statuses saved by an earlier run, kept up to date in the same file
+    pub fn open(
+        pipeline: Arc<PipelineManager>,
+        store: Arc<dyn SignatureStore>,
+        chunk_size: usize,
+        path: impl AsRef<Path>,
+    ) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let saved: Saved = if path.exists() {
+            let bytes = fs::read(path)
+                .with_context(|| format!("Failed to read revalidation state {}", path.display()))?;
+            serde_json::from_slice(&bytes)?
+        } else {
+            Saved::default()
+        };
+
+        let mut revalidator = Self::new(pipeline, store, chunk_size);
+        revalidator.statuses = RwLock::new(
+            saved
+                .statuses
+                .into_iter()
+                .map(|status| (status.signature_id, status))
+                .collect(),
+        );
+        revalidator.last_report = RwLock::new(saved.last_report);
+        revalidator.path = Some(path.to_path_buf());
+        Ok(revalidator)
+    }
+
+    pub fn last_report(&self) -> Option<RevalidationReport> {
+        self.last_report.read().unwrap().clone()
+    }
+
+    pub fn status(&self, id: Uuid) -> Option<SignatureStatus> {
+        self.statuses.read().unwrap().get(&id).cloned()
+    }
+
+    pub async fn run(&self) -> RevalidationReport {
+        let _running = self.running.lock().await;
+        let started_at = Utc::now();
+        let signatures = self.store.list();
+
+        let mut newly_failing = Vec::new();
+        let mut recovered = Vec::new();
+        let mut timed_out = 0;
+        for chunk in signatures.chunks(self.chunk_size) {
+            let results = self.pipeline.process_batch(chunk.to_vec()).await;
+
+            for (sig, result) in chunk.iter().zip(results) {
+                // says nothing about the signature, only that the run was short of time
+                if result.errors.iter().any(|e| e.code == ErrorCode::Timeout) {
+                    timed_out += 1;
+                    continue;
+                }
+                let Some(previous) = self.record(&result) else {
+                    continue;
+                };
+                let change = StatusChange {
+                    signature_id: sig.id,
+                    name: sig.name.clone(),
+                    email: sig.email.clone(),
+                    valid: result.valid,
+                    errors: result.errors,
+                };
+                if previous {
+                    newly_failing.push(change);
+                } else {
+                    recovered.push(change);
+                }
+            }
+        }
+
+        let failing = {
+            let mut statuses = self.statuses.write().unwrap();
+            // signatures removed from the store since the last run
+            let stored: HashSet<Uuid> = signatures.iter().map(|sig| sig.id).collect();
+            statuses.retain(|id, _| stored.contains(id));
+            statuses.values().filter(|status| !status.valid).count()
+        };
+
+        let report = RevalidationReport {
+            started_at,
+            finished_at: Utc::now(),
+            rules_version: self.pipeline.rules_version(),
+            checked: signatures.len(),
+            failing,
+            timed_out,
+            newly_failing,
+            recovered,
+        };
+        metrics::set_failing_signatures(failing);
+        info!(
+            checked = report.checked,
+            failing = report.failing,
+            timed_out = report.timed_out,
+            newly_failing = report.newly_failing.len(),
+            recovered = report.recovered.len(),
+            "Stored signatures revalidated"
+        );
+
+        *self.last_report.write().unwrap() = Some(report.clone());
+        self.save().await;
+        report
+    }
+
+    // written to a temporary file first, a crash midway leaves the previous state
+    async fn save(&self) {
+        let Some(path) = self.path.clone() else {
+            return;
+        };
+        let saved = Saved {
+            statuses: self.statuses.read().unwrap().values().cloned().collect(),
+            last_report: self.last_report(),
+        };
+
+        let written = tokio::task::spawn_blocking(move || -> anyhow::Result<()> {
+            let tmp = path.with_extension("saving");
+            fs::write(&tmp, serde_json::to_vec(&saved)?)
+                .with_context(|| format!("Failed to write {}", tmp.display()))?;
+            fs::rename(&tmp, &path)?;
+            Ok(())
+        })
+        .await;
+        match written {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => warn!(error = %e, "Failed to save revalidation state"),
+            Err(e) => warn!(error = %e, "Failed to save revalidation state"),
+        }
+    }
+
+    // run on the schedule, must be called from within a tokio runtime
+    pub fn schedule(self: Arc<Self>, schedule: Schedule) {
+        tokio::spawn(async move {
+            // taken from now on each turn, so a run longer than the interval skips a beat
+            while let Some(next) = schedule.upcoming(Utc).next() {
+                let wait = (next - Utc::now()).to_std().unwrap_or_default();
+                tokio::time::sleep(wait).await;
+                self.run().await;
+            }
+            warn!(schedule = %schedule, "Revalidation schedule has no upcoming runs");
+        });
+    }
+
+    // remember the new status, the previous one if it flipped
+    fn record(&self, result: &ValidationResult) -> Option<bool> {
+        let mut statuses = self.statuses.write().unwrap();
+        let now = Utc::now();
+        // stored signatures passed validation when they were submitted
+        let status = statuses
+            .entry(result.signature_id)
+            .or_insert(SignatureStatus {
+                signature_id: result.signature_id,
+                valid: true,
+                checked_at: now,
+                changed_at: None,
+            });
+
+        let previous = status.valid;
+        status.checked_at = now;
+        if previous == result.valid {
+            return None;
+        }
+
+        status.valid = result.valid;
+        status.changed_at = Some(now);
+        Some(previous)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::EmailSignature;
+    use crate::infrastructure::InMemorySignatureStore;
+
+    #[test]
+    fn test_parse_schedule() {
+        assert!(parse_schedule("0 3 * * *").is_ok());
+        assert!(parse_schedule("30 0 3 * * Mon-Fri").is_ok());
+        assert!(parse_schedule("every night").is_err());
+    }
+
+    // runs out of time on every signature
+    struct Expired;
+
+    #[async_trait::async_trait]
+    impl crate::pipeline::Stage for Expired {
+        fn name(&self) -> &'static str {
+            "expired"
+        }
+
+        async fn process(
+            &self,
+            items: &mut [&mut crate::pipeline::PipelineItem],
+            _ctx: &crate::pipeline::RunContext,
+        ) {
+            for item in items.iter_mut() {
+                item.time_out(self.name());
+            }
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_timed_out_signatures_keep_their_status() {
+        let store = Arc::new(InMemorySignatureStore::new());
+        let fine = EmailSignature::builder().email("jane@example.com").build();
+        store.put(fine.clone());
+        let pipeline = PipelineManager::builder()
+            .stage(Arc::new(Expired))
+            .build()
+            .unwrap();
+        let revalidator = Revalidator::new(Arc::new(pipeline), store, 10);
+
+        let report = revalidator.run().await;
+        assert_eq!(report.timed_out, 1);
+        assert_eq!(report.failing, 0);
+        assert!(report.newly_failing.is_empty());
+        assert!(revalidator.status(fine.id).is_none());
+    }
+
+    #[actix_web::test]
+    async fn test_statuses_survive_restart() {
+        let path = std::env::temp_dir().join(format!("revalidation-{}.json", Uuid::new_v4()));
+        let store = Arc::new(InMemorySignatureStore::new());
+        let outdated = EmailSignature::builder().email("invalid").build();
+        store.put(outdated.clone());
+        let open = || {
+            Revalidator::open(Arc::new(PipelineManager::new()), store.clone(), 10, &path).unwrap()
+        };
+
+        let report = open().run().await;
+        assert_eq!(report.newly_failing.len(), 1);
+
+        // after a restart it is still known to fail, not news
+        let revalidator = open();
+        assert!(!revalidator.status(outdated.id).unwrap().valid);
+        assert_eq!(
+            revalidator.last_report().unwrap().finished_at,
+            report.finished_at
+        );
+        let report = revalidator.run().await;
+        assert_eq!(report.failing, 1);
+        assert!(report.newly_failing.is_empty());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[actix_web::test]
+    async fn test_reports_status_changes() {
+        let store = Arc::new(InMemorySignatureStore::new());
+        let fine = EmailSignature::builder().email("jane@example.com").build();
+        // stored before the rules tightened
+        let mut outdated = EmailSignature::builder().email("invalid").build();
+        store.put(fine.clone());
+        store.put(outdated.clone());
+        let revalidator = Revalidator::new(Arc::new(PipelineManager::new()), store.clone(), 1);
+
+        let report = revalidator.run().await;
+        assert_eq!(report.checked, 2);
+        assert_eq!(report.failing, 1);
+        assert_eq!(report.newly_failing.len(), 1);
+        assert_eq!(report.newly_failing[0].signature_id, outdated.id);
+        assert_eq!(report.newly_failing[0].errors[0].field, "email");
+        assert!(report.recovered.is_empty());
+        assert!(revalidator.status(fine.id).unwrap().changed_at.is_none());
+
+        // still failing, but not news anymore
+        let report = revalidator.run().await;
+        assert_eq!(report.failing, 1);
+        assert!(report.newly_failing.is_empty());
+
+        outdated.email = "john@example.com".to_string();
+        store.put(outdated.clone());
+        let report = revalidator.run().await;
+        assert_eq!(report.failing, 0);
+        assert_eq!(report.recovered.len(), 1);
+        assert!(revalidator.status(outdated.id).unwrap().valid);
+        assert_eq!(
+            revalidator.last_report().unwrap().finished_at,
+            report.finished_at
+        );
+    }
+}
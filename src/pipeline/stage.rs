@@ -0,0 +1,178 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::domain::{EmailSignature, ErrorCode, ValidationError, ValidationResult};
+use crate::pipeline::pool::Lane;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Deadlines {
+    // time one signature may take in a single stage
+    pub signature_ms: u64,
+    // time a whole batch may take, what's left over comes back timed out
+    pub batch_ms: u64,
+}
+
+impl Default for Deadlines {
+    fn default() -> Self {
+        Self {
+            signature_ms: 5_000,
+            batch_ms: 60_000,
+        }
+    }
+}
+
+// asks a run to stop, stages check it between signatures
+#[derive(Debug, Clone, Default)]
+pub struct Cancellation(Arc<AtomicBool>);
+
+impl Cancellation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Release);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    // cancels once dropped, e.g. together with the future of a request whose client went away
+    pub fn guard(&self) -> CancelOnDrop {
+        CancelOnDrop(self.clone())
+    }
+}
+
+pub struct CancelOnDrop(Cancellation);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+// what a run of the pipeline has to respect, shared by all of its stages
+#[derive(Debug, Clone)]
+pub struct RunContext {
+    pub deadline: Instant,
+    pub signature_timeout: Duration,
+    pub cancellation: Cancellation,
+    // where stages run their CPU-bound work
+    pub lane: Lane,
+    // duplicates found across the whole batch by position, when the run only covers part of it
+    pub duplicates: Option<Arc<Vec<Vec<ValidationError>>>>,
+}
+
+impl RunContext {
+    pub fn new(deadlines: &Deadlines, cancellation: Cancellation, lane: Lane) -> Self {
+        Self {
+            deadline: Instant::now() + Duration::from_millis(deadlines.batch_ms),
+            signature_timeout: Duration::from_millis(deadlines.signature_ms),
+            cancellation,
+            lane,
+            duplicates: None,
+        }
+    }
+
+    pub fn expired(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    // time a signature starting now may take, bounded by the batch deadline
+    pub fn signature_budget(&self) -> Duration {
+        self.signature_timeout
+            .min(self.deadline.saturating_duration_since(Instant::now()))
+    }
+}
+
+// a signature on its way through the pipeline, with what the stages so far made of it
+pub struct PipelineItem {
+    // position in the submitted batch
+    pub index: usize,
+    pub signature: EmailSignature,
+    pub result: ValidationResult,
+    // set by the render stage
+    pub html: Option<String>,
+    halted: bool,
+    cancelled: bool,
+}
+
+impl PipelineItem {
+    pub fn new(index: usize, signature: EmailSignature) -> Self {
+        let result = ValidationResult {
+            signature_id: signature.id,
+            valid: true,
+            errors: Vec::new(),
+            warnings: Vec::new(),
+            validated_at: Utc::now(),
+        };
+
+        Self {
+            index,
+            signature,
+            result,
+            html: None,
+            halted: false,
+            cancelled: false,
+        }
+    }
+
+    // skip the remaining stages for this item, e.g. after it failed validation
+    pub fn halt(&mut self) {
+        self.halted = true;
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    // stopped before it was finished, its result shouldn't be reported
+    pub fn cancel(&mut self) {
+        self.cancelled = true;
+        self.halt();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    // give up on this item, reporting which stage ran out of time
+    pub fn time_out(&mut self, stage: &str) {
+        self.result.errors.push(
+            ValidationError::new(
+                "signature",
+                format!("Processing timed out during {}", stage),
+                ErrorCode::Timeout,
+            )
+            .with_param("stage", stage),
+        );
+        self.result.valid = false;
+        self.halt();
+    }
+
+    pub fn timed_out(&self) -> bool {
+        self.result
+            .errors
+            .iter()
+            .any(|e| e.code == ErrorCode::Timeout)
+    }
+}
+
+// one step of the pipeline, run over every item of a batch that hasn't been halted
+#[async_trait]
+pub trait Stage: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn process(&self, items: &mut [&mut PipelineItem], ctx: &RunContext);
+}
@@ -0,0 +1,331 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use futures_util::future::join_all;
+use rayon::prelude::*;
+
+use crate::domain::{
+    EmailSignature, ErrorCode, LinkValidator, SignatureValidator, ValidationError,
+    ValidationResult, duplicates,
+};
+use crate::infrastructure::{SignatureStore, metrics};
+use crate::pipeline::cache::{ValidationCache, cache_key};
+use crate::pipeline::pool::WorkerPool;
+use crate::pipeline::stage::{PipelineItem, RunContext, Stage};
+use crate::rendering::{DEFAULT_TEMPLATE_ID, TemplateRegistry};
+
+// names stages are configured by in `pipeline.stages`, in their usual order
+pub const STAGE_NAMES: &[&str] = &["normalize", "validate", "enrich", "render", "persist"];
+// the ones that only check signatures, without rendering or storing anything
+pub const VALIDATION_STAGES: &[&str] = &["normalize", "validate"];
+
+// canonical form of names, company and title
+pub struct NormalizeStage {
+    validator: Arc<SignatureValidator>,
+}
+
+impl NormalizeStage {
+    pub fn new(validator: Arc<SignatureValidator>) -> Self {
+        Self { validator }
+    }
+}
+
+#[async_trait]
+impl Stage for NormalizeStage {
+    fn name(&self) -> &'static str {
+        "normalize"
+    }
+
+    async fn process(&self, items: &mut [&mut PipelineItem], _ctx: &RunContext) {
+        for item in items.iter_mut() {
+            self.validator.normalize(&mut item.signature);
+        }
+    }
+}
+
+// validator and result cache, shared with the worker pool
+struct Rules {
+    validator: Arc<SignatureValidator>,
+    cache: Option<Arc<dyn ValidationCache>>,
+}
+
+impl Rules {
+    fn validate(&self, sig: &EmailSignature) -> ValidationResult {
+        let Some(cache) = &self.cache else {
+            return self.validator.validate(sig);
+        };
+
+        let key = cache_key(sig, &self.validator.rules_version());
+        if let Some(mut result) = cache.get(&key) {
+            metrics::record_cache_hit();
+            // same content under the same rules, so the result holds for this signature now
+            result.signature_id = sig.id;
+            result.validated_at = Utc::now();
+            return result;
+        }
+
+        metrics::record_cache_miss();
+        let result = self.validator.validate(sig);
+        // an unknown asset may be uploaded any moment, so that result can't be reused
+        if !result
+            .errors
+            .iter()
+            .any(|e| e.code == ErrorCode::UnknownAsset)
+        {
+            cache.put(key, result.clone());
+        }
+        result
+    }
+}
+
+// field and cross-field rules, duplicates and link reachability, halting invalid signatures
+pub struct ValidateStage {
+    rules: Arc<Rules>,
+    pool: Arc<WorkerPool>,
+    links: Option<LinkValidator>,
+    store: Option<Arc<dyn SignatureStore>>,
+}
+
+impl ValidateStage {
+    pub fn new(
+        validator: Arc<SignatureValidator>,
+        pool: Arc<WorkerPool>,
+        links: Option<LinkValidator>,
+        store: Option<Arc<dyn SignatureStore>>,
+        cache: Option<Arc<dyn ValidationCache>>,
+    ) -> Self {
+        Self {
+            rules: Arc::new(Rules { validator, cache }),
+            pool,
+            links,
+            store,
+        }
+    }
+
+    // hashes the whole batch and looks each signature up in the store, so batches do it on the pool
+    async fn check_duplicates(&self, items: &mut [&mut PipelineItem], ctx: &RunContext) {
+        if items.is_empty() {
+            return;
+        }
+        let sigs: Vec<(usize, EmailSignature)> = items
+            .iter()
+            .map(|item| (item.index, item.signature.clone()))
+            .collect();
+        let store = self.store.clone();
+        let in_batch = ctx.duplicates.clone();
+        let found = self
+            .pool
+            .run(ctx.lane, move || {
+                let sigs: Vec<(usize, &EmailSignature)> =
+                    sigs.iter().map(|(index, sig)| (*index, sig)).collect();
+                // only part of the batch is here, the rest was already looked through
+                let mut found = match &in_batch {
+                    Some(in_batch) => sigs
+                        .iter()
+                        .map(|(index, _)| in_batch[*index].clone())
+                        .collect(),
+                    None => duplicates::find_duplicates(&sigs),
+                };
+                if let Some(store) = &store {
+                    for ((_, sig), errors) in sigs.iter().zip(found.iter_mut()) {
+                        if let Some(stored) = store.find_other_by_email(&sig.email, sig.id) {
+                            errors.push(duplicates::duplicate_of_stored(&stored));
+                        }
+                    }
+                }
+                found
+            })
+            .await;
+
+        for (item, errors) in items.iter_mut().zip(found) {
+            add_errors(&mut item.result, errors);
+        }
+    }
+}
+
+enum Outcome {
+    Done(ValidationResult),
+    TimedOut,
+    Cancelled,
+}
+
+#[async_trait]
+impl Stage for ValidateStage {
+    fn name(&self) -> &'static str {
+        "validate"
+    }
+
+    async fn process(&self, items: &mut [&mut PipelineItem], ctx: &RunContext) {
+        let sigs: Vec<EmailSignature> = items.iter().map(|item| item.signature.clone()).collect();
+        // filled in as signatures finish, so the ones done by the deadline are kept
+        let outcomes: Arc<Vec<Mutex<Option<Outcome>>>> =
+            Arc::new(sigs.iter().map(|_| Mutex::new(None)).collect());
+        let abandoned = Arc::new(AtomicBool::new(false));
+
+        let rules = self.rules.clone();
+        let (deadline, timeout) = (ctx.deadline, ctx.signature_timeout);
+        let cancellation = ctx.cancellation.clone();
+        let (slots, given_up) = (outcomes.clone(), abandoned.clone());
+        let run = self.pool.run(ctx.lane, move || {
+            sigs.par_iter()
+                .zip(slots.par_iter())
+                .for_each(|(sig, slot)| {
+                    // a signature still waiting when the run is cancelled or out of time
+                    // isn't started, one that took too long doesn't count
+                    let outcome = if cancellation.is_cancelled() {
+                        Outcome::Cancelled
+                    } else if given_up.load(Ordering::Acquire) || Instant::now() >= deadline {
+                        Outcome::TimedOut
+                    } else {
+                        let start = Instant::now();
+                        let result = rules.validate(sig);
+                        if start.elapsed() > timeout {
+                            Outcome::TimedOut
+                        } else {
+                            Outcome::Done(result)
+                        }
+                    };
+                    *slot.lock().unwrap() = Some(outcome);
+                })
+        });
+
+        // the batch deadline holds even while signatures are still queued or running on the pool,
+        // whatever hasn't finished by then is skipped once a worker gets to it
+        if tokio::time::timeout_at(deadline.into(), run).await.is_err() {
+            abandoned.store(true, Ordering::Release);
+        }
+
+        for (item, slot) in items.iter_mut().zip(outcomes.iter()) {
+            match slot.lock().unwrap().take() {
+                Some(Outcome::Done(result)) => item.result = result,
+                Some(Outcome::Cancelled) => item.cancel(),
+                Some(Outcome::TimedOut) | None => item.time_out(self.name()),
+            }
+        }
+
+        let mut active: Vec<&mut PipelineItem> = items
+            .iter_mut()
+            .filter(|item| !item.is_halted())
+            .map(|item| &mut **item)
+            .collect();
+
+        self.check_duplicates(&mut active, ctx).await;
+
+        if let Some(links) = &self.links {
+            join_all(active.iter_mut().map(|item| async {
+                let check = links.check_reachability(&item.signature);
+                match tokio::time::timeout(ctx.signature_budget(), check).await {
+                    Ok(errors) => add_errors(&mut item.result, errors),
+                    // already invalid, it's reported as such rather than as timed out
+                    Err(_) if !item.result.valid => {}
+                    Err(_) => item.time_out("links"),
+                }
+            }))
+            .await;
+        }
+
+        for item in items.iter_mut().filter(|item| !item.result.valid) {
+            item.halt();
+        }
+    }
+}
+
+fn add_errors(result: &mut ValidationResult, errors: Vec<ValidationError>) {
+    if !errors.is_empty() {
+        result.errors.extend(errors);
+        result.valid = false;
+    }
+}
+
+// fills in what rendering needs, signatures pointing at a template that isn't
+// registered fall back to the default one
+pub struct EnrichStage {
+    templates: Arc<TemplateRegistry>,
+}
+
+impl EnrichStage {
+    pub fn new(templates: Arc<TemplateRegistry>) -> Self {
+        Self { templates }
+    }
+}
+
+#[async_trait]
+impl Stage for EnrichStage {
+    fn name(&self) -> &'static str {
+        "enrich"
+    }
+
+    async fn process(&self, items: &mut [&mut PipelineItem], _ctx: &RunContext) {
+        for item in items.iter_mut() {
+            let template_id = item.signature.template_id;
+            if self.templates.get(template_id).is_none() {
+                item.result.warnings.push(format!(
+                    "Template {} not found, using the default template",
+                    template_id
+                ));
+                item.signature.template_id = DEFAULT_TEMPLATE_ID;
+            }
+        }
+    }
+}
+
+pub struct RenderStage {
+    templates: Arc<TemplateRegistry>,
+}
+
+impl RenderStage {
+    pub fn new(templates: Arc<TemplateRegistry>) -> Self {
+        Self { templates }
+    }
+}
+
+#[async_trait]
+impl Stage for RenderStage {
+    fn name(&self) -> &'static str {
+        "render"
+    }
+
+    async fn process(&self, items: &mut [&mut PipelineItem], _ctx: &RunContext) {
+        for item in items.iter_mut() {
+            match self
+                .templates
+                .render(item.signature.template_id, &item.signature)
+            {
+                Ok(html) => item.html = Some(html),
+                Err(e) => {
+                    item.result
+                        .warnings
+                        .push(format!("Rendering failed: {}", e));
+                    item.halt();
+                }
+            }
+        }
+    }
+}
+
+// stores signatures that made it through every earlier stage
+pub struct PersistStage {
+    store: Arc<dyn SignatureStore>,
+}
+
+impl PersistStage {
+    pub fn new(store: Arc<dyn SignatureStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Stage for PersistStage {
+    fn name(&self) -> &'static str {
+        "persist"
+    }
+
+    async fn process(&self, items: &mut [&mut PipelineItem], _ctx: &RunContext) {
+        for item in items.iter() {
+            self.store.put(item.signature.clone());
+        }
+    }
+}
@@ -0,0 +1,497 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::domain::{ErrorCode, ValidationError};
+
+static TAG_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"<(/?)([a-zA-Z][a-zA-Z0-9]*)((?:"[^"]*"|'[^']*'|[^'">])*)>"#).unwrap()
+});
+static ATTR_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"([a-zA-Z_:][-a-zA-Z0-9_:.]*)(?:\s*=\s*("[^"]*"|'[^']*'|[^\s"'>]+))?"#).unwrap()
+});
+static PHONE_LIKE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\+?\d[\d\s().-]{6,}\d").unwrap());
+
+// WCAG AA for normal sized text
+const MIN_CONTRAST_RATIO: f64 = 4.5;
+const DEFAULT_TEXT_COLOR: Rgb = Rgb(0, 0, 0);
+const DEFAULT_BACKGROUND: Rgb = Rgb(255, 255, 255);
+
+// link text that says nothing about where the link goes
+const AMBIGUOUS_LINK_TEXT: &[&str] = &[
+    "click here",
+    "here",
+    "click",
+    "link",
+    "this link",
+    "more",
+    "read more",
+    "learn more",
+    "go",
+];
+
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track",
+    "wbr",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rgb(u8, u8, u8);
+
+// styles in effect for an open element
+struct Frame {
+    tag: String,
+    color: Rgb,
+    background: Rgb,
+}
+
+// an open <a>, collecting what a screen reader would announce for it
+struct OpenLink {
+    text: String,
+    // alt text of images inside the link, which names it when there's no text
+    image_alt: String,
+}
+
+// audit rendered html for common accessibility problems
+// the html is scanned tag by tag rather than fully parsed, which is enough for signature markup
+pub fn audit(html: &str) -> Vec<ValidationError> {
+    let mut findings = Vec::new();
+    let mut stack = vec![Frame {
+        tag: String::new(),
+        color: DEFAULT_TEXT_COLOR,
+        background: DEFAULT_BACKGROUND,
+    }];
+    let mut link: Option<OpenLink> = None;
+    let mut reported_pairs: Vec<(Rgb, Rgb)> = Vec::new();
+    let mut last_end = 0;
+    // open <style> or <script>, whose content nobody reads
+    let mut raw: Option<String> = None;
+
+    for caps in TAG_REGEX.captures_iter(html) {
+        let whole = caps.get(0).unwrap();
+        let closing = &caps[1] == "/";
+        let tag = caps[2].to_ascii_lowercase();
+
+        if let Some(open) = &raw {
+            if closing && tag == *open {
+                raw = None;
+            }
+            last_end = whole.end();
+            continue;
+        }
+
+        // text between the previous tag and this one
+        let text = decode_entities(html[last_end..whole.start()].trim());
+        last_end = whole.end();
+        if !text.is_empty() {
+            let frame = stack.last().unwrap();
+            check_contrast(&mut findings, &mut reported_pairs, &text, frame);
+            if let Some(link) = &mut link {
+                link.text.push_str(&text);
+                link.text.push(' ');
+            }
+        }
+
+        let attrs = parse_attrs(&caps[3]);
+
+        if closing {
+            if tag == "a"
+                && let Some(open) = link.take()
+            {
+                check_link(&mut findings, &open);
+            }
+            // pop back to the matching element, tolerating unclosed children
+            if let Some(pos) = stack.iter().rposition(|f| f.tag == tag) {
+                stack.truncate(pos);
+            }
+            continue;
+        }
+
+        match tag.as_str() {
+            "img" => {
+                let alt = attr(&attrs, "alt");
+                check_image(&mut findings, &attrs, alt, link.is_some());
+                if let (Some(link), Some(alt)) = (&mut link, alt) {
+                    link.image_alt.push_str(alt.trim());
+                }
+            }
+            "a" => {
+                link = Some(OpenLink {
+                    text: String::new(),
+                    image_alt: String::new(),
+                })
+            }
+            "style" | "script" => {
+                raw = Some(tag);
+                continue;
+            }
+            _ => {}
+        }
+
+        if VOID_ELEMENTS.contains(&tag.as_str()) || caps[3].trim_end().ends_with('/') {
+            continue;
+        }
+
+        let parent = stack.last().unwrap();
+        let (color, background) = attr(&attrs, "style")
+            .map(|style| styled_colors(style, parent))
+            .unwrap_or((parent.color, parent.background));
+        stack.push(Frame {
+            tag,
+            color,
+            background,
+        });
+    }
+
+    findings
+}
+
+fn check_image(
+    findings: &mut Vec<ValidationError>,
+    attrs: &[(String, String)],
+    alt: Option<&str>,
+    in_link: bool,
+) {
+    let src = attr(attrs, "src").unwrap_or_default();
+
+    match alt.map(str::trim) {
+        None => findings.push(
+            ValidationError::new(
+                "html.img",
+                format!("Image {} has no alt text", src),
+                ErrorCode::MissingAltText,
+            )
+            .with_param("src", src),
+        ),
+        // empty alt marks decorative images, but a link made of one has no name
+        Some("") if in_link => findings.push(
+            ValidationError::new(
+                "html.img",
+                format!("Linked image {} has empty alt text", src),
+                ErrorCode::MissingAltText,
+            )
+            .with_param("src", src),
+        ),
+        Some(alt) if looks_like_text(alt) => findings.push(
+            ValidationError::new(
+                "html.img",
+                format!(
+                    "Image {} appears to contain text ({}), use real text instead",
+                    src, alt
+                ),
+                ErrorCode::TextAsImage,
+            )
+            .with_param("src", src),
+        ),
+        _ => {}
+    }
+
+    // a short, very wide image is almost always a line of text
+    let dimension =
+        |name| attr(attrs, name).and_then(|v| v.trim_end_matches("px").parse::<u32>().ok());
+    if let (Some(width), Some(height)) = (dimension("width"), dimension("height"))
+        && height > 0
+        && height <= 40
+        && width >= height * 5
+    {
+        findings.push(
+            ValidationError::new(
+                "html.img",
+                format!(
+                    "Image {} is {}x{}, which suggests text rendered as an image",
+                    src, width, height
+                ),
+                ErrorCode::TextAsImage,
+            )
+            .with_param("src", src),
+        );
+    }
+}
+
+// contact details or whole sentences in alt text mean the image is standing in for text
+fn looks_like_text(alt: &str) -> bool {
+    alt.contains('@') || PHONE_LIKE_REGEX.is_match(alt) || alt.split_whitespace().count() > 8
+}
+
+fn check_link(findings: &mut Vec<ValidationError>, link: &OpenLink) {
+    let text = link.text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let name = if text.is_empty() {
+        link.image_alt.as_str()
+    } else {
+        text.as_str()
+    };
+
+    if name.is_empty() {
+        findings.push(
+            ValidationError::new("html.a", "Link has no text", ErrorCode::AmbiguousLinkText)
+                .with_param("text", ""),
+        );
+        return;
+    }
+
+    let normalized = name
+        .trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase();
+    if AMBIGUOUS_LINK_TEXT.contains(&normalized.as_str()) {
+        findings.push(
+            ValidationError::new(
+                "html.a",
+                format!("Link text \"{}\" doesn't describe its destination", name),
+                ErrorCode::AmbiguousLinkText,
+            )
+            .with_param("text", name),
+        );
+    }
+}
+
+fn check_contrast(
+    findings: &mut Vec<ValidationError>,
+    reported: &mut Vec<(Rgb, Rgb)>,
+    text: &str,
+    frame: &Frame,
+) {
+    let ratio = contrast_ratio(frame.color, frame.background);
+    if ratio >= MIN_CONTRAST_RATIO || reported.contains(&(frame.color, frame.background)) {
+        return;
+    }
+    reported.push((frame.color, frame.background));
+
+    let text = truncate(text, 40);
+    let ratio = format!("{:.2}", ratio);
+    findings.push(
+        ValidationError::new(
+            "html.text",
+            format!(
+                "Text \"{}\" has contrast ratio {} between {} and {} (min {})",
+                text, ratio, frame.color, frame.background, MIN_CONTRAST_RATIO
+            ),
+            ErrorCode::LowContrast,
+        )
+        .with_param("text", &text)
+        .with_param("ratio", ratio)
+        .with_param("color", frame.color)
+        .with_param("background", frame.background)
+        .with_param("min", MIN_CONTRAST_RATIO),
+    );
+}
+
+// text and background colour after applying an inline style on top of the parent's
+fn styled_colors(style: &str, parent: &Frame) -> (Rgb, Rgb) {
+    let mut color = parent.color;
+    let mut background = parent.background;
+
+    for declaration in style.split(';') {
+        let Some((property, value)) = declaration.split_once(':') else {
+            continue;
+        };
+        let property = property.trim().to_ascii_lowercase();
+        let value = value.trim().trim_end_matches("!important").trim();
+
+        match property.as_str() {
+            "color" => color = parse_color(value).unwrap_or(color),
+            "background-color" => background = parse_color(value).unwrap_or(background),
+            // the shorthand may hold more than a colour, use the first token that parses
+            "background" => {
+                if let Some(c) = value.split_whitespace().find_map(parse_color) {
+                    background = c;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    (color, background)
+}
+
+fn parse_color(value: &str) -> Option<Rgb> {
+    let value = value.trim().to_ascii_lowercase();
+
+    if let Some(hex) = value.strip_prefix('#') {
+        let channel = |s: &str| u8::from_str_radix(s, 16).ok();
+        return match hex.len() {
+            3 => {
+                let c: Vec<u8> = hex
+                    .chars()
+                    .map(|c| channel(&c.to_string()).map(|v| v * 17))
+                    .collect::<Option<_>>()?;
+                Some(Rgb(c[0], c[1], c[2]))
+            }
+            6 => Some(Rgb(
+                channel(&hex[0..2])?,
+                channel(&hex[2..4])?,
+                channel(&hex[4..6])?,
+            )),
+            _ => None,
+        };
+    }
+
+    if let Some(args) = value
+        .strip_prefix("rgb(")
+        .or_else(|| value.strip_prefix("rgba("))
+        .and_then(|v| v.strip_suffix(')'))
+    {
+        let c: Vec<u8> = args
+            .split(',')
+            .take(3)
+            .map(|v| v.trim().parse().ok())
+            .collect::<Option<_>>()?;
+        return (c.len() == 3).then(|| Rgb(c[0], c[1], c[2]));
+    }
+
+    match value.as_str() {
+        "black" => Some(Rgb(0, 0, 0)),
+        "white" => Some(Rgb(255, 255, 255)),
+        "gray" | "grey" => Some(Rgb(128, 128, 128)),
+        "silver" => Some(Rgb(192, 192, 192)),
+        "lightgray" | "lightgrey" => Some(Rgb(211, 211, 211)),
+        "red" => Some(Rgb(255, 0, 0)),
+        "green" => Some(Rgb(0, 128, 0)),
+        "blue" => Some(Rgb(0, 0, 255)),
+        "navy" => Some(Rgb(0, 0, 128)),
+        "yellow" => Some(Rgb(255, 255, 0)),
+        "orange" => Some(Rgb(255, 165, 0)),
+        _ => None,
+    }
+}
+
+// WCAG 2 relative luminance
+fn luminance(c: Rgb) -> f64 {
+    let channel = |v: u8| {
+        let v = v as f64 / 255.0;
+        if v <= 0.03928 {
+            v / 12.92
+        } else {
+            ((v + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * channel(c.0) + 0.7152 * channel(c.1) + 0.0722 * channel(c.2)
+}
+
+fn contrast_ratio(a: Rgb, b: Rgb) -> f64 {
+    let (la, lb) = (luminance(a), luminance(b));
+    (la.max(lb) + 0.05) / (la.min(lb) + 0.05)
+}
+
+impl std::fmt::Display for Rgb {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+fn parse_attrs(raw: &str) -> Vec<(String, String)> {
+    ATTR_REGEX
+        .captures_iter(raw)
+        .map(|caps| {
+            let value = caps
+                .get(2)
+                .map(|v| v.as_str().trim_matches(|c| c == '"' || c == '\''))
+                .unwrap_or_default();
+            (caps[1].to_ascii_lowercase(), decode_entities(value))
+        })
+        .collect()
+}
+
+fn attr<'a>(attrs: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    attrs
+        .iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, v)| v.as_str())
+}
+
+// the entities handlebars escapes
+fn decode_entities(s: &str) -> String {
+    s.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#x27;", "'")
+        .replace("&#x60;", "`")
+        .replace("&#x3D;", "=")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+fn truncate(s: &str, max_chars: usize) -> String {
+    match s.char_indices().nth(max_chars) {
+        Some((i, _)) => format!("{}...", &s[..i]),
+        None => s.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn codes(html: &str) -> Vec<ErrorCode> {
+        audit(html).into_iter().map(|e| e.code).collect()
+    }
+
+    #[test]
+    fn test_accessible_markup() {
+        let html = r##"<table style="color: #333333;"><tr><td>
+            <img src="https://cdn.example.com/me.png" alt="Jane Doe" width="80" height="80">
+            <strong>Jane Doe</strong><br>
+            <a href="https://example.com" style="color: #1a73e8;">example.com</a>
+            <a href="https://example.com/promo"><img src="https://cdn.example.com/b.png" alt="Spring sale"></a>
+        </td></tr></table>"##;
+
+        assert!(audit(html).is_empty(), "{:?}", audit(html));
+    }
+
+    #[test]
+    fn test_images() {
+        assert!(matches!(
+            codes(r#"<img src="a.png">"#).as_slice(),
+            [ErrorCode::MissingAltText]
+        ));
+        assert!(codes(r#"<img src="a.png" alt="">"#).is_empty());
+        assert!(matches!(
+            codes(r#"<a href="/x"><img src="a.png" alt=""></a>"#).as_slice(),
+            [ErrorCode::MissingAltText, ErrorCode::AmbiguousLinkText]
+        ));
+        assert!(matches!(
+            codes(r#"<img src="a.png" alt="jane@example.com">"#).as_slice(),
+            [ErrorCode::TextAsImage]
+        ));
+        assert!(matches!(
+            codes(r#"<img src="a.png" alt="Jane" width="300" height="20">"#).as_slice(),
+            [ErrorCode::TextAsImage]
+        ));
+    }
+
+    #[test]
+    fn test_contrast() {
+        let findings = audit(
+            r#"<div style="background-color: #ffffff;"><span style="color: #cccccc;">faint</span> <span style="color: #cccccc">again</span></div>"#,
+        );
+        // reported once per colour pair
+        assert_eq!(findings.len(), 1);
+        assert!(matches!(findings[0].code, ErrorCode::LowContrast));
+        assert!(findings[0].message.contains("#cccccc"));
+
+        // background inherited from the parent
+        assert!(matches!(
+            codes(r#"<td style="background: navy"><p style="color: #000080">hidden</p></td>"#)
+                .as_slice(),
+            [ErrorCode::LowContrast]
+        ));
+        assert!(codes(r#"<p style="color: #fff; background: #000">ok</p>"#).is_empty());
+    }
+
+    #[test]
+    fn test_skips_style_and_script() {
+        assert!(
+            codes(
+                r#"<div style="color: #eeeeee"><style>td { color: #eeeeee; }</style><script>if (a < b) { x("<a href='/x'>here</a>"); }</script></div>"#
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_link_text() {
+        assert!(matches!(
+            codes(r#"<a href="/x">Click here!</a>"#).as_slice(),
+            [ErrorCode::AmbiguousLinkText]
+        ));
+        assert!(codes(r#"<a href="/x">Book a meeting</a>"#).is_empty());
+    }
+}
@@ -0,0 +1,245 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::{ErrorCode, ValidationError};
+
+static STYLE_ATTR_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)\sstyle\s*=\s*("[^"]*"|'[^']*')"#).unwrap());
+static STYLE_BLOCK_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<style[^>]*>.*?</style>").unwrap());
+static DATA_IMAGE_REGEX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(?i)data:image/[^"')\s]+"#).unwrap());
+
+// rough size of the headers of one MIME part
+const MIME_PART_OVERHEAD: usize = 200;
+
+// size limits for a rendered signature, warn and max are inclusive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SizeBudget {
+    pub html_warn_bytes: usize,
+    pub html_max_bytes: usize,
+    pub mime_warn_bytes: usize,
+    pub mime_max_bytes: usize,
+}
+
+impl Default for SizeBudget {
+    // gmail clips whole messages over ~102KB, the signature should use a fraction of that
+    fn default() -> Self {
+        Self {
+            html_warn_bytes: 10 * 1024,
+            html_max_bytes: 32 * 1024,
+            mime_warn_bytes: 50 * 1024,
+            mime_max_bytes: 90 * 1024,
+        }
+    }
+}
+
+// where the bytes of a rendered signature come from
+#[derive(Debug, Clone, Serialize)]
+pub struct SizeBreakdown {
+    pub html_bytes: usize,
+    pub markup_bytes: usize,
+    pub inline_style_bytes: usize,
+    // data: uris embedded in the html
+    pub embedded_image_bytes: usize,
+    // images attached as inline MIME parts
+    pub attached_image_bytes: usize,
+    // estimated size of the multipart/related message carrying the signature
+    pub mime_bytes: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BudgetStatus {
+    Ok,
+    Warning,
+    Exceeded,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SizeReport {
+    pub status: BudgetStatus,
+    pub breakdown: SizeBreakdown,
+    pub budget: SizeBudget,
+    pub findings: Vec<ValidationError>,
+}
+
+// measure a rendered signature, `inline_images` are the sizes of images sent as MIME parts
+pub fn measure(html: &str, inline_images: &[usize]) -> SizeBreakdown {
+    let inline_style_bytes: usize = STYLE_ATTR_REGEX
+        .find_iter(html)
+        .chain(STYLE_BLOCK_REGEX.find_iter(html))
+        .map(|m| m.len())
+        .sum();
+    let embedded_image_bytes: usize = DATA_IMAGE_REGEX.find_iter(html).map(|m| m.len()).sum();
+
+    // html goes out quoted-printable, images base64 encoded
+    let mut mime_bytes = MIME_PART_OVERHEAD + quoted_printable_len(html);
+    for size in inline_images {
+        mime_bytes += MIME_PART_OVERHEAD + base64_len(*size);
+    }
+
+    SizeBreakdown {
+        html_bytes: html.len(),
+        markup_bytes: html
+            .len()
+            .saturating_sub(inline_style_bytes + embedded_image_bytes),
+        inline_style_bytes,
+        embedded_image_bytes,
+        attached_image_bytes: inline_images.iter().sum(),
+        mime_bytes,
+    }
+}
+
+pub fn check(breakdown: SizeBreakdown, budget: &SizeBudget) -> SizeReport {
+    let mut findings = Vec::new();
+    let mut status = BudgetStatus::Ok;
+
+    let checks = [
+        (
+            "html",
+            "Rendered HTML",
+            breakdown.html_bytes,
+            budget.html_warn_bytes,
+            budget.html_max_bytes,
+        ),
+        (
+            "mime",
+            "Message with inline images",
+            breakdown.mime_bytes,
+            budget.mime_warn_bytes,
+            budget.mime_max_bytes,
+        ),
+    ];
+
+    for (field, label, size, warn, max) in checks {
+        let (level, limit) = if size > max {
+            (BudgetStatus::Exceeded, max)
+        } else if size > warn {
+            (BudgetStatus::Warning, warn)
+        } else {
+            continue;
+        };
+
+        let verb = if level == BudgetStatus::Exceeded {
+            "exceeds"
+        } else {
+            "is over the recommended"
+        };
+        findings.push(ValidationError::new(
+            field,
+            format!(
+                "{} is {} and {} {} budget ({})",
+                label,
+                format_bytes(size),
+                verb,
+                format_bytes(limit),
+                contributors(&breakdown)
+            ),
+            ErrorCode::TooLarge,
+        ));
+        status = status.max(level);
+    }
+
+    SizeReport {
+        status,
+        breakdown,
+        budget: budget.clone(),
+        findings,
+    }
+}
+
+// largest contributors first
+fn contributors(breakdown: &SizeBreakdown) -> String {
+    let mut parts = [
+        (
+            "images",
+            breakdown.embedded_image_bytes + breakdown.attached_image_bytes,
+        ),
+        ("inline styles", breakdown.inline_style_bytes),
+        ("markup", breakdown.markup_bytes),
+    ];
+    parts.sort_by_key(|(_, bytes)| std::cmp::Reverse(*bytes));
+
+    parts
+        .iter()
+        .filter(|(_, bytes)| *bytes > 0)
+        .map(|(name, bytes)| format!("{} {}", name, format_bytes(*bytes)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn format_bytes(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    }
+}
+
+// base64 with CRLF every 76 characters
+fn base64_len(bytes: usize) -> usize {
+    let encoded = bytes.div_ceil(3) * 4;
+    encoded + encoded.div_ceil(76) * 2
+}
+
+// escaped bytes grow to 3, plus a soft line break every 76 characters
+fn quoted_printable_len(text: &str) -> usize {
+    let encoded: usize = text
+        .bytes()
+        .map(|b| match b {
+            b'=' => 3,
+            b'\n' | b'\r' | b'\t' | 0x20..=0x7E => 1,
+            _ => 3,
+        })
+        .sum();
+    encoded + encoded.div_ceil(76) * 3
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure_breakdown() {
+        let html = r#"<p style="color: red;">Hi</p><img src="data:image/png;base64,AAAA">"#;
+        let breakdown = measure(html, &[3000]);
+
+        assert_eq!(breakdown.html_bytes, html.len());
+        assert_eq!(
+            breakdown.inline_style_bytes,
+            r#" style="color: red;""#.len()
+        );
+        assert_eq!(
+            breakdown.embedded_image_bytes,
+            "data:image/png;base64,AAAA".len()
+        );
+        assert_eq!(breakdown.attached_image_bytes, 3000);
+        assert!(breakdown.mime_bytes > 4000);
+    }
+
+    #[test]
+    fn test_check_budget() {
+        let budget = SizeBudget {
+            html_warn_bytes: 10,
+            html_max_bytes: 100,
+            mime_warn_bytes: 10_000,
+            mime_max_bytes: 20_000,
+        };
+
+        let report = check(measure(&"x".repeat(50), &[]), &budget);
+        assert_eq!(report.status, BudgetStatus::Warning);
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(report.findings[0].field, "html");
+
+        let report = check(measure("<p>hi</p>", &[30_000]), &budget);
+        assert_eq!(report.status, BudgetStatus::Exceeded);
+        assert!(
+            report.findings[0]
+                .message
+                .starts_with("Message with inline images")
+        );
+        assert!(report.findings[0].message.contains("images 29.3 KB"));
+    }
+}
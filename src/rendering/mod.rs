@@ -0,0 +1,8 @@
+pub mod accessibility;
+pub mod budget;
+pub mod preview;
+pub mod templates;
+pub mod vcard;
+
+pub use budget::{BudgetStatus, SizeBudget, SizeReport};
+pub use templates::{DEFAULT_TEMPLATE_ID, SignatureTemplate, TemplateRegistry};
@@ -0,0 +1,39 @@
+// widths used for the side by side preview
+pub const DESKTOP_WIDTH_PX: u32 = 600;
+pub const MOBILE_WIDTH_PX: u32 = 320;
+
+// wrap a rendered signature in a standalone page showing it at desktop and mobile widths
+pub fn preview_page(template_name: &str, signature_html: &str) -> String {
+    let title = handlebars::html_escape(template_name);
+
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Preview: {title}</title>
+<style>
+  body {{ font-family: sans-serif; background: #f2f2f2; margin: 24px; }}
+  .previews {{ display: flex; gap: 32px; align-items: flex-start; }}
+  .frame {{ background: #ffffff; border: 1px solid #cccccc; padding: 16px; overflow: hidden; }}
+  h2 {{ font-size: 13px; color: #666666; margin: 0 0 8px 0; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+<div class="previews">
+  <section>
+    <h2>Desktop ({desktop}px)</h2>
+    <div class="frame" style="width: {desktop}px;">{signature_html}</div>
+  </section>
+  <section>
+    <h2>Mobile ({mobile}px)</h2>
+    <div class="frame" style="width: {mobile}px;">{signature_html}</div>
+  </section>
+</div>
+</body>
+</html>"#,
+        desktop = DESKTOP_WIDTH_PX,
+        mobile = MOBILE_WIDTH_PX,
+    )
+}
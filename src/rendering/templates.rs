@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::RwLock;
+
+use handlebars::Handlebars;
+use serde::Serialize;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::domain::EmailSignature;
+use crate::error::AppError;
+
+// id of the built-in template, always registered
+pub const DEFAULT_TEMPLATE_ID: Uuid = Uuid::nil();
+
+const DEFAULT_TEMPLATE: &str = r#"<table cellpadding="0" cellspacing="0" style="font-family: Arial, sans-serif; font-size: 14px; color: #333333;">
+  <tr>
+    {{#if photo_url}}<td style="padding-right: 12px; vertical-align: top;"><img src="{{photo_url}}" alt="{{name}}" width="80" height="80" style="border-radius: 40px;"></td>{{/if}}
+    <td style="vertical-align: top;">
+      <strong style="font-size: 16px;">{{name}}</strong>{{#if pronouns}} <span style="color: #666666;">({{pronouns}})</span>{{/if}}
+      {{#if title}}<br><span>{{title}}</span>{{/if}}{{#if department}}<br><span>{{department}}</span>{{/if}}{{#if company}}<br><span>{{company}}</span>{{/if}}
+      <br><a href="mailto:{{email}}" style="color: #1a73e8;">{{email}}</a>
+      {{#if phone}}<br><span>Office: {{phone}}</span>{{/if}}{{#if mobile_phone}}<br><span>Mobile: {{mobile_phone}}</span>{{/if}}
+      {{#if website}}<br><a href="{{website}}" style="color: #1a73e8;">{{website}}</a>{{/if}}
+      {{#if address}}<br><span>{{#if address.street}}{{address.street}}, {{/if}}{{#if address.city}}{{address.city}}{{/if}}{{#if address.region}}, {{address.region}}{{/if}}{{#if address.postal_code}} {{address.postal_code}}{{/if}}{{#if address.country}}, {{address.country}}{{/if}}</span>{{/if}}
+      {{#if social.linkedin}}<br><a href="{{social.linkedin}}" style="color: #1a73e8;">LinkedIn</a>{{/if}}{{#if social.x}} <a href="{{social.x}}" style="color: #1a73e8;">X</a>{{/if}}{{#if social.github}} <a href="{{social.github}}" style="color: #1a73e8;">GitHub</a>{{/if}}
+    </td>
+  </tr>
+  {{#if logo_url}}<tr>
+    <td colspan="2" style="padding-top: 8px;"><img src="{{logo_url}}" alt="{{#if company}}{{company}}{{else}}Logo{{/if}}" height="40" style="display: block;"></td>
+  </tr>{{/if}}
+  {{#if banner}}<tr>
+    <td colspan="2" style="padding-top: 12px;">{{#if banner.link_url}}<a href="{{banner.link_url}}">{{/if}}<img src="{{banner.image_url}}" alt="{{banner.alt_text}}" width="400" style="display: block;">{{#if banner.link_url}}</a>{{/if}}</td>
+  </tr>{{/if}}
+</table>"#;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SignatureTemplate {
+    pub id: Uuid,
+    pub name: String,
+    pub html: String,
+}
+
+// holds the known templates and renders signatures with them
+pub struct TemplateRegistry {
+    templates: RwLock<HashMap<Uuid, SignatureTemplate>>,
+    handlebars: RwLock<Handlebars<'static>>,
+}
+
+impl TemplateRegistry {
+    pub fn new() -> Self {
+        let registry = Self {
+            templates: RwLock::new(HashMap::new()),
+            handlebars: RwLock::new(Handlebars::new()),
+        };
+
+        registry
+            .register(SignatureTemplate {
+                id: DEFAULT_TEMPLATE_ID,
+                name: "default".to_string(),
+                html: DEFAULT_TEMPLATE.to_string(),
+            })
+            .expect("built-in template must compile");
+
+        registry
+    }
+
+    // register every `<uuid>.hbs` file in a directory, returns how many were loaded
+    pub fn load_dir(&self, dir: impl AsRef<Path>) -> anyhow::Result<usize> {
+        let mut loaded = 0;
+
+        for entry in std::fs::read_dir(dir.as_ref())? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("hbs") {
+                continue;
+            }
+
+            let Some(id) = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| Uuid::parse_str(s).ok())
+            else {
+                warn!(path = %path.display(), "Skipping template, file name is not a UUID");
+                continue;
+            };
+
+            let html = std::fs::read_to_string(&path)?;
+            self.register(SignatureTemplate {
+                id,
+                name: id.to_string(),
+                html,
+            })
+            .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+            loaded += 1;
+        }
+
+        info!(count = loaded, "Loaded signature templates");
+        Ok(loaded)
+    }
+
+    pub fn register(&self, template: SignatureTemplate) -> Result<(), AppError> {
+        self.handlebars
+            .write()
+            .unwrap()
+            .register_template_string(&template.id.to_string(), &template.html)
+            .map_err(|e| AppError::Render(e.to_string()))?;
+
+        self.templates
+            .write()
+            .unwrap()
+            .insert(template.id, template);
+        Ok(())
+    }
+
+    pub fn get(&self, id: Uuid) -> Option<SignatureTemplate> {
+        self.templates.read().unwrap().get(&id).cloned()
+    }
+
+    pub fn render(&self, template_id: Uuid, sig: &EmailSignature) -> Result<String, AppError> {
+        if !self.templates.read().unwrap().contains_key(&template_id) {
+            return Err(AppError::NotFound(format!(
+                "Template {} not found",
+                template_id
+            )));
+        }
+
+        self.handlebars
+            .read()
+            .unwrap()
+            .render(&template_id.to_string(), sig)
+            .map_err(|e| AppError::Render(e.to_string()))
+    }
+}
+
+impl Default for TemplateRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_default_template() {
+        let registry = TemplateRegistry::new();
+        let sig = EmailSignature::builder()
+            .name("Jane <Doe>")
+            .title("Engineer")
+            .build();
+
+        let html = registry.render(DEFAULT_TEMPLATE_ID, &sig).unwrap();
+        assert!(html.contains("Jane &lt;Doe&gt;"));
+        assert!(html.contains("Engineer"));
+        assert!(!html.contains("{{"));
+        assert!(!html.contains("LinkedIn"));
+    }
+
+    #[test]
+    fn test_render_extended_fields() {
+        let registry = TemplateRegistry::new();
+        let sig = EmailSignature::builder()
+            .pronouns("they/them")
+            .mobile_phone("+44 7700 900123")
+            .social(crate::domain::SocialLinks {
+                github: Some("https://github.com/octocat".to_string()),
+                ..Default::default()
+            })
+            .build();
+
+        let html = registry.render(DEFAULT_TEMPLATE_ID, &sig).unwrap();
+        assert!(html.contains("(they/them)"));
+        assert!(html.contains("Mobile: +44 7700 900123"));
+        assert!(html.contains(r#"href="https://github.com/octocat""#));
+    }
+
+    #[test]
+    fn test_default_template_is_accessible() {
+        let registry = TemplateRegistry::new();
+        let sig = EmailSignature::builder()
+            .pronouns("she/her")
+            .website("https://example.com")
+            .photo_url("https://cdn.example.com/me.png")
+            .logo_url("https://cdn.example.com/logo.png")
+            .company("Example Corp")
+            .build();
+
+        let html = registry.render(DEFAULT_TEMPLATE_ID, &sig).unwrap();
+        let findings = crate::rendering::accessibility::audit(&html);
+        assert!(findings.is_empty(), "{:?}", findings);
+    }
+
+    #[test]
+    fn test_render_unknown_template() {
+        let registry = TemplateRegistry::new();
+        let sig = EmailSignature::builder().build();
+
+        let result = registry.render(Uuid::new_v4(), &sig);
+        assert!(matches!(result, Err(AppError::NotFound(_))));
+    }
+}
@@ -0,0 +1,189 @@
+use crate::domain::{EmailSignature, normalize_phone};
+
+pub const VCARD_CONTENT_TYPE: &str = "text/vcard; charset=utf-8";
+
+// vCard 4.0 (RFC 6350) export of a signature's contact details
+pub fn to_vcard(sig: &EmailSignature) -> String {
+    let mut lines = vec![
+        "BEGIN:VCARD".to_string(),
+        "VERSION:4.0".to_string(),
+        format!("UID:urn:uuid:{}", sig.id),
+        format!("FN:{}", escape(sig.name.trim())),
+        format!("N:{}", structured_name(&sig.name)),
+        format!("EMAIL;TYPE=work:{}", escape(sig.email.trim())),
+    ];
+
+    if let Some(phone) = &sig.phone {
+        // fall back to the raw value if it can't be normalized into a tel: uri
+        match normalize_phone(phone) {
+            Some(normalized) => lines.push(format!("TEL;VALUE=uri;TYPE=work:tel:{}", normalized)),
+            None => lines.push(format!("TEL;VALUE=text;TYPE=work:{}", escape(phone.trim()))),
+        }
+    }
+
+    if let Some(mobile) = &sig.mobile_phone {
+        match normalize_phone(mobile) {
+            Some(normalized) => lines.push(format!("TEL;VALUE=uri;TYPE=cell:tel:{}", normalized)),
+            None => lines.push(format!(
+                "TEL;VALUE=text;TYPE=cell:{}",
+                escape(mobile.trim())
+            )),
+        }
+    }
+
+    if let Some(company) = &sig.company {
+        lines.push(format!("ORG:{}", escape(company.trim())));
+    }
+
+    if let Some(title) = &sig.title {
+        lines.push(format!("TITLE:{}", escape(title.trim())));
+    }
+
+    if let Some(address) = &sig.address {
+        // ADR is po box;extended;street;locality;region;postal code;country
+        let part = |v: &Option<String>| escape(v.as_deref().unwrap_or_default().trim());
+        lines.push(format!(
+            "ADR;TYPE=work:;;{};{};{};{};{}",
+            part(&address.street),
+            part(&address.city),
+            part(&address.region),
+            part(&address.postal_code),
+            part(&address.country)
+        ));
+    }
+
+    if let Some(website) = &sig.website {
+        lines.push(format!("URL:{}", escape(website.trim())));
+    }
+
+    lines.push(format!("REV:{}", sig.created_at.format("%Y%m%dT%H%M%SZ")));
+    lines.push("END:VCARD".to_string());
+
+    let mut out = String::new();
+    for line in lines {
+        fold_line(&line, &mut out);
+    }
+    out
+}
+
+// suggested download name for the exported file
+pub fn file_name(sig: &EmailSignature) -> String {
+    let base: String = sig
+        .name
+        .trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+
+    if base.trim_matches('_').is_empty() {
+        format!("{}.vcf", sig.id)
+    } else {
+        format!("{}.vcf", base)
+    }
+}
+
+// N is family;given;additional;prefixes;suffixes - we only know a single display name,
+// so the last word is treated as the family name
+fn structured_name(name: &str) -> String {
+    let mut parts: Vec<&str> = name.split_whitespace().collect();
+    let family = if parts.len() > 1 {
+        parts.pop().unwrap()
+    } else {
+        ""
+    };
+    let given = parts.join(" ");
+
+    format!("{};{};;;", escape(family), escape(&given))
+}
+
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ',' => out.push_str("\\,"),
+            ';' => out.push_str("\\;"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+// content lines are folded at 75 octets without splitting utf-8 sequences
+fn fold_line(line: &str, out: &mut String) {
+    let mut width = 0;
+    for c in line.chars() {
+        let len = c.len_utf8();
+        if width + len > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += len;
+    }
+    out.push_str("\r\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ical::VcardParser;
+
+    fn parse(vcard: &str) -> ical::parser::vcard::component::VcardContact {
+        let mut parser = VcardParser::new(vcard.as_bytes());
+        let contact = parser.next().expect("one contact").expect("valid vcard");
+        assert!(parser.next().is_none());
+        contact
+    }
+
+    fn property<'a>(
+        contact: &'a ical::parser::vcard::component::VcardContact,
+        name: &str,
+    ) -> Option<&'a str> {
+        contact
+            .properties
+            .iter()
+            .find(|p| p.name == name)
+            .and_then(|p| p.value.as_deref())
+    }
+
+    #[test]
+    fn test_vcard_round_trip() {
+        let sig = EmailSignature::builder()
+            .name("Ada King Lovelace")
+            .email("ada@example.com")
+            .phone("+44 (20) 7946-0958")
+            .company("Analytical Engines Ltd")
+            .title("Programmer")
+            .build();
+
+        let vcard = to_vcard(&sig);
+        let contact = parse(&vcard);
+
+        assert_eq!(property(&contact, "VERSION"), Some("4.0"));
+        assert_eq!(property(&contact, "FN"), Some("Ada King Lovelace"));
+        assert_eq!(property(&contact, "N"), Some("Lovelace;Ada King;;;"));
+        assert_eq!(property(&contact, "EMAIL"), Some("ada@example.com"));
+        assert_eq!(property(&contact, "TEL"), Some("tel:+442079460958"));
+        assert_eq!(property(&contact, "ORG"), Some("Analytical Engines Ltd"));
+        assert_eq!(property(&contact, "TITLE"), Some("Programmer"));
+    }
+
+    #[test]
+    fn test_vcard_folds_long_lines() {
+        let sig = EmailSignature::builder()
+            .title("Principal Staff Engineer, Platform Reliability & Developer Experience, EMEA")
+            .build();
+
+        let vcard = to_vcard(&sig);
+        assert!(vcard.split("\r\n").all(|line| line.len() <= 75));
+
+        let contact = parse(&vcard);
+        assert_eq!(
+            property(&contact, "TITLE"),
+            Some("Principal Staff Engineer\\, Platform Reliability & Developer Experience\\, EMEA")
+        );
+    }
+}